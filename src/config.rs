@@ -0,0 +1,285 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BranchingHeuristic {
+    // Uniformly random unassigned variable and polarity.
+    Random,
+    // Smallest unassigned variable, numeric names ordered numerically, set to false.
+    FirstUnassigned,
+    // Literal maximizing the sum of 2^-|C| over the unsatisfied clauses C containing it.
+    JeroslowWang,
+    // Literal occurring in the most unsatisfied clauses.
+    Dlis,
+    // First unassigned variable in the given order, set to false. Variables
+    // the order leaves out are decided after it, smallest first.
+    StaticOrder(Vec<String>),
+    // Unassigned variable with the highest activity, set to false. Activities
+    // start at the number of occurrences, grow whenever the variable is in a
+    // learned clause and decay by `vsids_decay` after every conflict.
+    Vsids,
+}
+
+impl fmt::Display for BranchingHeuristic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BranchingHeuristic::Random => "random",
+            BranchingHeuristic::FirstUnassigned => "first",
+            BranchingHeuristic::JeroslowWang => "jw",
+            BranchingHeuristic::Dlis => "dlis",
+            BranchingHeuristic::Vsids => "vsids",
+            BranchingHeuristic::StaticOrder(order) => {
+                return write!(f, "static:{}", order.join(","));
+            }
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for BranchingHeuristic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BranchingHeuristic, String> {
+        match s {
+            "random" => Ok(BranchingHeuristic::Random),
+            "first" => Ok(BranchingHeuristic::FirstUnassigned),
+            "jw" => Ok(BranchingHeuristic::JeroslowWang),
+            "dlis" => Ok(BranchingHeuristic::Dlis),
+            "vsids" => Ok(BranchingHeuristic::Vsids),
+            _ if s.starts_with("static:") => Ok(BranchingHeuristic::StaticOrder(
+                s["static:".len()..]
+                    .split(',')
+                    .filter(|variable| !variable.is_empty())
+                    .map(String::from)
+                    .collect(),
+            )),
+            _ => Err(format!("unknown branching heuristic '{}'", s)),
+        }
+    }
+}
+
+// How a model fills in the variables the search left unassigned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FreePolicy {
+    AllTrue,
+    AllFalse,
+    // Left out of the model.
+    Omit,
+    // Left out of the model's values and listed as free instead.
+    Mark,
+}
+
+// How much of the search the solver writes to its output. `Info` leaves out
+// the per-assignment trace but keeps restarts, deletions and other events.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Verbosity {
+    Silent,
+    Info,
+    Trace,
+}
+
+impl fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Verbosity::Silent => "silent",
+            Verbosity::Info => "info",
+            Verbosity::Trace => "trace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Verbosity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Verbosity, String> {
+        match s {
+            "silent" => Ok(Verbosity::Silent),
+            "info" => Ok(Verbosity::Info),
+            "trace" => Ok(Verbosity::Trace),
+            _ => Err(format!("unknown verbosity '{}'", s)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SolverConfig {
+    pub branching: BranchingHeuristic,
+    // Seeds every random choice, entropy is used when unset.
+    pub seed: Option<u64>,
+    // Factor every VSIDS activity is multiplied by after each conflict.
+    pub vsids_decay: f64,
+    // Debug builds only: recheck every propagation round against a plain scan.
+    pub verify_propagation: bool,
+    // Debug builds only: check the assignment invariants after propagation
+    // and after every backjump.
+    pub check_invariants: bool,
+    // Clauses longer than this are propagated through two watched literals
+    // rather than rescanned on every pass, which pays off for long clauses but
+    // costs more bookkeeping than it saves on short ones.
+    pub watch_threshold: Option<usize>,
+    // Scan learned clauses, the most recent first, before the original ones
+    // during unit propagation.
+    pub learned_first: bool,
+    // Backjumps of more than this many levels are replaced by backtracking a
+    // single level, keeping the learned clause. Learned units still jump to 0.
+    pub chrono_threshold: Option<usize>,
+    // The search restarts from level 0, keeping what it learned, after this
+    // many conflicts times the next term of the Luby sequence.
+    pub restart_base: Option<u64>,
+    // Every this many conflicts, half of the learned clauses are deleted,
+    // those spanning the most decision levels first. Clauses that are the
+    // reason for an assignment and learned units are kept.
+    pub reduce_interval: Option<u64>,
+    // Every this many conflicts, the saved phases are reset to the values of
+    // the longest conflict-free trail seen so far.
+    pub rephase_interval: Option<u64>,
+    // Simplify the formula before the first solve. Skipped while a proof is
+    // being logged, as removing clauses would renumber them.
+    pub preprocess: bool,
+    // Preprocessing stops when this runs out, and solving goes ahead with the
+    // formula as simplified so far.
+    #[cfg(feature = "std")]
+    pub preprocess_timeout: Option<Duration>,
+    // Before each solve, set every literal whose negation occurs in no clause
+    // true at level 0 and drop the clauses it satisfies, until none is left.
+    // The clauses come back when the solver is reset, as clauses added or
+    // assumptions made later can make such literals wrong. Skipped while a
+    // proof is being logged.
+    pub pure_literals: bool,
+    pub free_variable_policy: FreePolicy,
+    // Solve each connected component of the formula on its own and combine
+    // the models. Skipped while a proof is being logged.
+    pub decompose: bool,
+    // Check every learned clause against the formula by brute force and say
+    // whether it is entailed. Only done for formulas over at most
+    // `TEACHING_MAX_VARIABLES` variables.
+    pub teaching: bool,
+    // Silent unless asked for, so that a solver used as a library writes
+    // nothing to stdout.
+    pub verbosity: Verbosity,
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig {
+            branching: BranchingHeuristic::Random,
+            seed: None,
+            vsids_decay: 0.95,
+            verify_propagation: false,
+            check_invariants: false,
+            watch_threshold: None,
+            learned_first: false,
+            chrono_threshold: None,
+            restart_base: Some(100),
+            reduce_interval: Some(2000),
+            rephase_interval: None,
+            preprocess: false,
+            #[cfg(feature = "std")]
+            preprocess_timeout: None,
+            pure_literals: false,
+            free_variable_policy: FreePolicy::AllTrue,
+            decompose: false,
+            teaching: false,
+            verbosity: Verbosity::Silent,
+        }
+    }
+}
+
+impl SolverConfig {
+    pub fn branching(mut self, branching: BranchingHeuristic) -> SolverConfig {
+        self.branching = branching;
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> SolverConfig {
+        self.seed = seed;
+        self
+    }
+
+    pub fn vsids_decay(mut self, vsids_decay: f64) -> SolverConfig {
+        self.vsids_decay = vsids_decay;
+        self
+    }
+
+    pub fn verify_propagation(mut self, verify_propagation: bool) -> SolverConfig {
+        self.verify_propagation = verify_propagation;
+        self
+    }
+
+    pub fn check_invariants(mut self, check_invariants: bool) -> SolverConfig {
+        self.check_invariants = check_invariants;
+        self
+    }
+
+    pub fn watch_threshold(mut self, watch_threshold: Option<usize>) -> SolverConfig {
+        self.watch_threshold = watch_threshold;
+        self
+    }
+
+    pub fn learned_first(mut self, learned_first: bool) -> SolverConfig {
+        self.learned_first = learned_first;
+        self
+    }
+
+    pub fn chrono_threshold(mut self, chrono_threshold: Option<usize>) -> SolverConfig {
+        self.chrono_threshold = chrono_threshold;
+        self
+    }
+
+    pub fn restart_base(mut self, restart_base: Option<u64>) -> SolverConfig {
+        self.restart_base = restart_base;
+        self
+    }
+
+    pub fn reduce_interval(mut self, reduce_interval: Option<u64>) -> SolverConfig {
+        self.reduce_interval = reduce_interval;
+        self
+    }
+
+    pub fn rephase_interval(mut self, rephase_interval: Option<u64>) -> SolverConfig {
+        self.rephase_interval = rephase_interval;
+        self
+    }
+
+    pub fn preprocess(mut self, preprocess: bool) -> SolverConfig {
+        self.preprocess = preprocess;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn preprocess_timeout(mut self, preprocess_timeout: Option<Duration>) -> SolverConfig {
+        self.preprocess_timeout = preprocess_timeout;
+        self
+    }
+
+    pub fn pure_literals(mut self, pure_literals: bool) -> SolverConfig {
+        self.pure_literals = pure_literals;
+        self
+    }
+
+    pub fn free_variable_policy(mut self, free_variable_policy: FreePolicy) -> SolverConfig {
+        self.free_variable_policy = free_variable_policy;
+        self
+    }
+
+    pub fn decompose(mut self, decompose: bool) -> SolverConfig {
+        self.decompose = decompose;
+        self
+    }
+
+    pub fn teaching(mut self, teaching: bool) -> SolverConfig {
+        self.teaching = teaching;
+        self
+    }
+
+    pub fn verbosity(mut self, verbosity: Verbosity) -> SolverConfig {
+        self.verbosity = verbosity;
+        self
+    }
+}
@@ -0,0 +1,97 @@
+use crate::collections::HashMap;
+use crate::wff::Clause;
+use crate::wff::Formula;
+use crate::wff::Literal;
+use crate::wff::Variable;
+
+// Exact number of models over `formula.variables`. The formula is split into
+// connected components whose counts multiply, each variable no clause
+// mentions contributing a factor of 2, and a component is counted by
+// branching on its most frequent variable and splitting again. None when
+// the count does not fit in a u128, as with 128 variables and no clauses.
+pub fn count_models<V: Variable>(formula: &Formula<V>) -> Option<u128> {
+    if formula
+        .clauses
+        .iter()
+        .any(|clause| clause.literals.is_empty())
+    {
+        return Some(0);
+    }
+    if formula.clauses.is_empty() {
+        return 1u128.checked_shl(formula.variables.len() as u32);
+    }
+    let components = formula.connected_components();
+    if components.len() > 1 {
+        let mut count = Some(1u128);
+        for component in &components {
+            match count_models(component) {
+                // Too many models elsewhere do not matter then.
+                Some(0) => return Some(0),
+                factor => count = count.zip(factor).and_then(|(a, b)| a.checked_mul(b)),
+            }
+        }
+        return count;
+    }
+
+    let mut occurrences: HashMap<&V, usize> = HashMap::new();
+    for literal in formula.literals() {
+        *occurrences.entry(&literal.value).or_insert(0) += 1;
+    }
+    let variable = occurrences
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| {
+            a_count.cmp(b_count).then_with(|| b.key().cmp(&a.key()))
+        })
+        .map(|(variable, _)| variable.clone())
+        .unwrap();
+    let positive = count_models(&condition(formula, &Literal::new(variable.clone(), false)));
+    let negative = count_models(&condition(formula, &Literal::new(variable, true)));
+    positive.zip(negative).and_then(|(a, b)| a.checked_add(b))
+}
+
+// The formula with `literal` fixed true: clauses containing it are dropped,
+// its negation is removed from the others, and its variable is removed.
+fn condition<V: Variable>(formula: &Formula<V>, literal: &Literal<V>) -> Formula<V> {
+    let falsified = literal.negate();
+    let clauses = formula
+        .clauses
+        .iter()
+        .filter(|clause| !clause.literals.contains(literal))
+        .map(|clause| {
+            Clause::new(
+                clause
+                    .literals
+                    .iter()
+                    .filter(|l| **l != falsified)
+                    .cloned()
+                    .collect(),
+            )
+        })
+        .collect();
+    let mut variables = formula.variables.clone();
+    variables.remove(&literal.value);
+    Formula { clauses, variables }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_models() {
+        let formula = crate::parse_text_cnf("a b\n-a c\n").unwrap();
+        assert_eq!(count_models(&formula), Some(4));
+        let formula = crate::parse_dimacs_cnf("p cnf 3 2\n1 0\n-1 0\n").unwrap();
+        assert_eq!(count_models(&formula), Some(0));
+    }
+
+    #[test]
+    fn counts_past_u128_are_none() {
+        let formula = crate::parse_dimacs_cnf("p cnf 127 0\n").unwrap();
+        assert_eq!(count_models(&formula), Some(1 << 127));
+        let formula = crate::parse_dimacs_cnf("p cnf 200 0\n").unwrap();
+        assert_eq!(count_models(&formula), None);
+        let formula = crate::parse_dimacs_cnf("p cnf 130 1\n1 2 0\n").unwrap();
+        assert_eq!(count_models(&formula), None);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::parser::ParseError;
+use alloc::string::String;
+use core::error;
+use core::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    Parse(ParseError),
+    // A variable the formula does not mention.
+    UnknownVariable(String),
+    // A solver invariant that did not hold, which is a bug in the solver.
+    Internal(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "parse error: {}", e),
+            Error::UnknownVariable(variable) => write!(f, "unknown variable '{}'", variable),
+            Error::Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Error {
+        Error::Parse(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::CdclSolver;
+    use crate::wff::Literal;
+
+    #[test]
+    fn malformed_input_is_a_parse_error() {
+        assert_eq!(
+            crate::parse_dimacs_cnf("p cnf 1 1\n2 0\n").err(),
+            Some(Error::Parse(ParseError::VariableOutOfRange {
+                line: 2,
+                variable: String::from("2"),
+                declared: Some(1),
+            }))
+        );
+        assert_eq!(
+            crate::parse_dimacs_cnf("p cnf 2 2\n1 2 0\n").err(),
+            Some(Error::Parse(ParseError::ClauseCount {
+                declared: 2,
+                found: 1,
+            }))
+        );
+        assert_eq!(
+            crate::parse_dimacs_cnf("p cnf 4294967296 0\n").err(),
+            Some(Error::Parse(ParseError::VariableOutOfRange {
+                line: 1,
+                variable: String::from("4294967296"),
+                declared: None,
+            }))
+        );
+        assert_eq!(
+            crate::parse_text_cnf("a --b\n").err(),
+            Some(Error::Parse(ParseError::InvalidLiteral {
+                line: 1,
+                token: String::from("--b"),
+            }))
+        );
+        assert_eq!(
+            crate::parser::parse_wff("a ∧").err(),
+            Some(Error::Parse(ParseError::UnexpectedEnd))
+        );
+    }
+
+    #[test]
+    fn unknown_variables_are_errors() {
+        let mut solver = CdclSolver::new(crate::parse_text_cnf("a b\n").unwrap());
+        let unknown = Error::UnknownVariable(String::from("c"));
+        assert_eq!(solver.set_branching_priority("c", 1), Err(unknown));
+        let (a, c) = (
+            Literal::new(String::from("a"), false),
+            Literal::new(String::from("c"), false),
+        );
+        assert_eq!(
+            solver.relation(&a, &c).err(),
+            Some(Error::UnknownVariable(String::from("c")))
+        );
+    }
+
+    #[test]
+    fn a_sound_clause_db_verifies() {
+        let mut solver = CdclSolver::new(crate::parse_text_cnf("a b\n-a b\n-b c\n").unwrap());
+        solver.solve();
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+    }
+}
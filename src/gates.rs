@@ -0,0 +1,370 @@
+use crate::collections::BTreeMap;
+use crate::collections::HashMap;
+use crate::collections::HashSet;
+use crate::wff::variable_key;
+use crate::wff::Clause;
+use crate::wff::Formula;
+use crate::wff::Literal;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Gate {
+    // output ↔ (inputs[0] ∧ inputs[1] ∧ ...)
+    And {
+        output: String,
+        inputs: Vec<Literal>,
+    },
+    // output ↔ (inputs[0] ∨ inputs[1] ∨ ...)
+    Or {
+        output: String,
+        inputs: Vec<Literal>,
+    },
+    // output ↔ (inputs.0 ⊕ inputs.1)
+    Xor {
+        output: String,
+        inputs: (Literal, Literal),
+    },
+    // output ↔ (condition ? then_branch : else_branch)
+    Ite {
+        output: String,
+        condition: String,
+        then_branch: Literal,
+        else_branch: Literal,
+    },
+}
+
+// Recognizes gate definitions written out as clauses: AND and OR gates over
+// two or more inputs, and XOR and if-then-else gates over three variables.
+// The clauses of a gate may appear anywhere in the formula and in any order.
+pub fn extract_gates(formula: &Formula) -> Vec<Gate> {
+    let mut gates = and_or_gates(formula);
+    let (xor_gates, xor_variables) = xor_gates(formula);
+    gates.extend(xor_gates);
+    gates.extend(ite_gates(formula, &xor_variables));
+    gates
+}
+
+fn literal_key(literal: &Literal) -> ((u64, &str), bool) {
+    (variable_key(&literal.value), literal.negation)
+}
+
+fn sorted_pair(a: Literal, b: Literal) -> (Literal, Literal) {
+    if literal_key(&a) <= literal_key(&b) {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Literals of a clause over three distinct variables, otherwise None.
+fn ternary_literals(clause: &Clause) -> Option<Vec<Literal>> {
+    let mut literals = clause.literals.clone();
+    literals.sort_by(|a, b| literal_key(a).cmp(&literal_key(b)));
+    literals.dedup();
+    let distinct = literals
+        .iter()
+        .map(|l| &l.value)
+        .collect::<HashSet<_>>()
+        .len();
+    if literals.len() == 3 && distinct == 3 {
+        Some(literals)
+    } else {
+        None
+    }
+}
+
+// A clause (p ∨ l1 ∨ ... ∨ ln) together with the binary clauses (¬p ∨ ¬li)
+// states p ↔ (¬l1 ∧ ... ∧ ¬ln). A positive p is the output of an AND gate;
+// a negative p makes its variable the output of an OR gate over the li.
+fn and_or_gates(formula: &Formula) -> Vec<Gate> {
+    let binaries: HashSet<(Literal, Literal)> = formula
+        .clauses
+        .iter()
+        .filter(|clause| clause.literals.len() == 2)
+        .map(|clause| sorted_pair(clause.literals[0].clone(), clause.literals[1].clone()))
+        .collect();
+
+    let mut gates: Vec<Gate> = Vec::new();
+    for clause in &formula.clauses {
+        if clause.literals.len() < 3 {
+            continue;
+        }
+        for pivot in &clause.literals {
+            let others: Vec<&Literal> = clause.literals.iter().filter(|l| *l != pivot).collect();
+            if others.len() + 1 != clause.literals.len()
+                || others.iter().any(|l| l.value == pivot.value)
+            {
+                continue;
+            }
+            let defined = others
+                .iter()
+                .all(|l| binaries.contains(&sorted_pair(pivot.negate(), l.negate())));
+            if !defined {
+                continue;
+            }
+            let output = pivot.value.clone();
+            if pivot.negation {
+                let inputs = others.into_iter().cloned().collect();
+                gates.push(Gate::Or { output, inputs });
+            } else {
+                let inputs = others.iter().map(|l| l.negate()).collect();
+                gates.push(Gate::And { output, inputs });
+            }
+        }
+    }
+    gates
+}
+
+// The four ternary clauses over x, y, z with an odd number of negations each
+// state z ↔ (x ⊕ y); with an even number they state z ↔ (¬x ⊕ y). The
+// variable that sorts last is reported as the output.
+fn xor_gates(formula: &Formula) -> (Vec<Gate>, HashSet<Vec<String>>) {
+    let mut patterns: BTreeMap<Vec<(u64, String)>, HashSet<Vec<bool>>> = BTreeMap::new();
+    for clause in &formula.clauses {
+        if let Some(literals) = ternary_literals(clause) {
+            let variables = literals
+                .iter()
+                .map(|l| (variable_key(&l.value).0, l.value.clone()))
+                .collect();
+            let signs = literals.iter().map(|l| l.negation).collect();
+            patterns.entry(variables).or_default().insert(signs);
+        }
+    }
+
+    let mut gates: Vec<Gate> = Vec::new();
+    let mut used: HashSet<Vec<String>> = HashSet::new();
+    for (variables, signs) in patterns {
+        let odd = signs
+            .iter()
+            .filter(|s| s.iter().filter(|n| **n).count() % 2 == 1)
+            .count();
+        let even = signs.len() - odd;
+        if odd != 4 && even != 4 {
+            continue;
+        }
+        let names: Vec<String> = variables.into_iter().map(|(_, name)| name).collect();
+        let x = Literal::new(names[0].clone(), even == 4);
+        let y = Literal::new(names[1].clone(), false);
+        gates.push(Gate::Xor {
+            output: names[2].clone(),
+            inputs: (x, y),
+        });
+        used.insert(names);
+    }
+    (gates, used)
+}
+
+// The clause pairs (c ∨ l1 ∨ l2), (c ∨ ¬l1 ∨ ¬l2) fix l1 ↔ ¬l2 when c is
+// false. A variable o shared by such a pair for c and one for ¬c is the output
+// of an if-then-else gate on c. Triples already read as XOR are skipped.
+fn ite_gates(formula: &Formula, xor_variables: &HashSet<Vec<String>>) -> Vec<Gate> {
+    let mut pairs: HashSet<(Literal, (Literal, Literal))> = HashSet::new();
+    for clause in &formula.clauses {
+        if let Some(literals) = ternary_literals(clause) {
+            for (i, x) in literals.iter().enumerate() {
+                let rest: Vec<Literal> = literals
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, l)| l.clone())
+                    .collect();
+                pairs.insert((x.clone(), sorted_pair(rest[0].clone(), rest[1].clone())));
+            }
+        }
+    }
+
+    // For each condition literal x, the pairs (l1, l2) whose complement is
+    // also present, each listed once.
+    let mut sides: HashMap<Literal, Vec<(Literal, Literal)>> = HashMap::new();
+    for (x, (l1, l2)) in &pairs {
+        let complement = sorted_pair(l1.negate(), l2.negate());
+        if literal_key(l1) < literal_key(&complement.0) && pairs.contains(&(x.clone(), complement))
+        {
+            sides
+                .entry(x.clone())
+                .or_default()
+                .push((l1.clone(), l2.clone()));
+        }
+    }
+    for side in sides.values_mut() {
+        side.sort_by(|a, b| {
+            (literal_key(&a.0), literal_key(&a.1)).cmp(&(literal_key(&b.0), literal_key(&b.1)))
+        });
+    }
+
+    let mut conditions: Vec<&String> = sides
+        .keys()
+        .filter(|x| !x.negation && sides.contains_key(&x.negate()))
+        .map(|x| &x.value)
+        .collect();
+    conditions.sort_by(|a, b| variable_key(a).cmp(&variable_key(b)));
+
+    let mut gates: Vec<Gate> = Vec::new();
+    for condition in conditions {
+        let when_false = &sides[&Literal::new(condition.clone(), false)];
+        let when_true = &sides[&Literal::new(condition.clone(), true)];
+        for (a1, a2) in when_false {
+            for (b1, b2) in when_true {
+                let mut triple = vec![
+                    condition.clone(),
+                    a1.value.clone(),
+                    a2.value.clone(),
+                    b1.value.clone(),
+                    b2.value.clone(),
+                ];
+                triple.sort_by(|a, b| variable_key(a).cmp(&variable_key(b)));
+                triple.dedup();
+                if triple.len() == 3 && xor_variables.contains(&triple) {
+                    continue;
+                }
+                for output in [&a1.value, &a2.value] {
+                    if *output != b1.value && *output != b2.value {
+                        continue;
+                    }
+                    gates.push(Gate::Ite {
+                        output: output.clone(),
+                        condition: condition.clone(),
+                        then_branch: value_of_output(output, b1, b2),
+                        else_branch: value_of_output(output, a1, a2),
+                    });
+                }
+            }
+        }
+    }
+    gates
+}
+
+// Given l1 ↔ ¬l2 where one of them is over `output`, the literal `output`
+// is equivalent to.
+fn value_of_output(output: &str, l1: &Literal, l2: &Literal) -> Literal {
+    let (own, other) = if l1.value == output {
+        (l1, l2)
+    } else {
+        (l2, l1)
+    };
+    if own.negation {
+        other.clone()
+    } else {
+        other.negate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_text_cnf;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn literal(text: &str) -> Literal {
+        text.parse().unwrap()
+    }
+
+    fn holds(literal: &Literal, model: &HashMap<String, bool>) -> bool {
+        model[&literal.value] != literal.negation
+    }
+
+    // Whether the output of `gate` has the value its definition gives it.
+    fn consistent(gate: &Gate, model: &HashMap<String, bool>) -> bool {
+        let (output, value) = match gate {
+            Gate::And { output, inputs } => (output, inputs.iter().all(|l| holds(l, model))),
+            Gate::Or { output, inputs } => (output, inputs.iter().any(|l| holds(l, model))),
+            Gate::Xor { output, inputs } => {
+                (output, holds(&inputs.0, model) != holds(&inputs.1, model))
+            }
+            Gate::Ite {
+                output,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let branch = if model[condition] {
+                    then_branch
+                } else {
+                    else_branch
+                };
+                (output, holds(branch, model))
+            }
+        };
+        model[output] == value
+    }
+
+    #[test]
+    fn known_encodings_are_recognized() {
+        // Clauses of each gate, shuffled among each other.
+        let formula = parse_text_cnf(
+            "-o a\n-x -y z\n-c -t p\nc e -p\n-o b\nq r\no -a -b\nx y z\n-x y -z\nx -y -z\n-c t -p\nc -e p\n-q -r\n",
+        )
+        .unwrap();
+        let gates = extract_gates(&formula);
+        let and = Gate::And {
+            output: String::from("o"),
+            inputs: vec![literal("a"), literal("b")],
+        };
+        let xor = Gate::Xor {
+            output: String::from("z"),
+            inputs: (literal("¬x"), literal("y")),
+        };
+        let ite = Gate::Ite {
+            output: String::from("p"),
+            condition: String::from("c"),
+            then_branch: literal("t"),
+            else_branch: literal("e"),
+        };
+        assert!(gates.contains(&and));
+        assert!(gates.contains(&xor));
+        assert!(gates.contains(&ite));
+        // `q ↔ ¬r` is only binary clauses, which define no gate.
+        for gate in &gates {
+            assert!(!matches!(
+                gate,
+                Gate::And { output, .. } | Gate::Or { output, .. } if output == "q" || output == "r"
+            ));
+        }
+    }
+
+    #[test]
+    fn recognized_gates_hold_in_every_model() {
+        let names = ["a", "b", "c", "d"];
+        let mut rng = StdRng::seed_from_u64(226);
+        let mut found = 0;
+        for _ in 0..3000 {
+            let mut text = String::new();
+            for _ in 0..rng.gen_range(3..7) {
+                for _ in 0..rng.gen_range(2..4) {
+                    if rng.gen() {
+                        text.push('-');
+                    }
+                    text.push_str(names[rng.gen_range(0..names.len())]);
+                    text.push(' ');
+                }
+                text.push('\n');
+            }
+            let formula = parse_text_cnf(&text).unwrap();
+            let gates = extract_gates(&formula);
+            found += gates.len();
+            let variables: Vec<String> = formula.variables.iter().cloned().collect();
+            for bits in 0..1u32 << variables.len() {
+                let model: HashMap<String, bool> = variables
+                    .iter()
+                    .enumerate()
+                    .map(|(i, variable)| (variable.clone(), bits >> i & 1 == 1))
+                    .collect();
+                let satisfies = formula
+                    .clauses
+                    .iter()
+                    .all(|clause| clause.literals.iter().any(|l| holds(l, &model)));
+                if !satisfies {
+                    continue;
+                }
+                for gate in &gates {
+                    assert!(consistent(gate, &model), "gate broken in {}", text);
+                }
+            }
+        }
+        assert!(found > 0);
+    }
+}
@@ -0,0 +1,495 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use collections::HashSet;
+use error::Error;
+use parser::ParseError;
+use wff::WeightedFormula;
+
+// Hash maps come from `std`, or from `hashbrown` without it.
+mod collections {
+    pub use alloc::collections::BTreeMap;
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::HashMap;
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::HashSet;
+    #[cfg(feature = "std")]
+    pub use std::collections::HashMap;
+    #[cfg(feature = "std")]
+    pub use std::collections::HashSet;
+}
+
+// The clock deadlines are checked against. Without `std` there is none, and
+// so no deadline to pass.
+mod time {
+    #[cfg(feature = "std")]
+    pub use std::time::Instant;
+
+    #[cfg(not(feature = "std"))]
+    #[derive(Clone, Copy, Debug)]
+    pub enum Instant {}
+
+    #[cfg(feature = "std")]
+    pub fn passed(deadline: Instant) -> bool {
+        Instant::now() >= deadline
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn passed(deadline: Instant) -> bool {
+        match deadline {}
+    }
+}
+
+pub mod config;
+pub mod counting;
+pub mod error;
+pub mod gates;
+pub mod maxsat;
+pub mod model;
+pub mod mus;
+pub mod parser;
+pub mod preprocess;
+pub mod proof;
+pub mod reference;
+pub mod search_tree;
+pub mod solver;
+pub mod statistics;
+pub mod tseitin;
+pub mod wff;
+
+pub use solver::CdclSolver;
+pub use solver::SolverResult;
+pub use wff::Clause;
+pub use wff::Formula;
+pub use wff::Literal;
+pub use wff::StrLiteral;
+
+// A parsed DIMACS CNF problem, with anything suspicious about the input that
+// did not stop it from being read. Variables keep their DIMACS numbers.
+pub struct DimacsCnf {
+    pub formula: Formula<u32>,
+    pub warnings: Vec<String>,
+}
+
+pub fn parse_dimacs_cnf(content: &str) -> Result<Formula<u32>, Error> {
+    parse_dimacs_cnf_with_warnings(content).map(|cnf| cnf.formula)
+}
+
+// A `p cnf N M` header must declare at least as many variables as are used
+// and exactly as many clauses as are given. Variables 1..=N are part of the
+// formula even when no clause mentions them. Without a header the clauses
+// are taken as they are, with a warning.
+pub fn parse_dimacs_cnf_with_warnings(content: &str) -> Result<DimacsCnf, Error> {
+    let mut warnings: Vec<String> = Vec::new();
+    let (clauses, header) = read_dimacs(content, &mut warnings)?;
+    let mut formula = Formula::new(clauses);
+    match header {
+        Some(variables) => formula.variables.extend(1..=variables as u32),
+        None => warnings.push(String::from("no `p cnf` header, counts not checked")),
+    }
+    Ok(DimacsCnf { formula, warnings })
+}
+
+// The clauses of a DIMACS CNF with its variables kept as numbers, checked
+// against the header as `parse_dimacs_cnf` does.
+pub fn parse_dimacs_clauses(content: &str) -> Result<Vec<Clause<u32>>, Error> {
+    Ok(read_dimacs(content, &mut Vec::new())?.0)
+}
+
+// The clauses, and the number of variables the header declares once its
+// counts have been checked. Reading stops at a line starting with `%`, the
+// trailer SATLIB files end with, with a warning.
+fn read_dimacs(
+    content: &str,
+    warnings: &mut Vec<String>,
+) -> Result<(Vec<Clause<u32>>, Option<usize>), ParseError> {
+    let mut clauses = vec![Clause::new(Vec::new())];
+    let mut header: Option<(usize, usize)> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.first().is_some_and(|token| token.starts_with('%')) {
+            warnings.push(format!("ignored everything from the `%` on line {}", i + 1));
+            break;
+        }
+        if tokens.first() == Some(&"p") {
+            let counts = match tokens[..] {
+                [_, "cnf", variables, clauses] => variables.parse().ok().zip(clauses.parse().ok()),
+                _ => None,
+            };
+            let (variables, count): (usize, usize) =
+                counts.ok_or(ParseError::InvalidHeader { line: i + 1 })?;
+            // Variables are read as u32s, and so must be declared as them.
+            if u32::try_from(variables).is_err() {
+                return Err(ParseError::VariableOutOfRange {
+                    line: i + 1,
+                    variable: variables.to_string(),
+                    declared: None,
+                });
+            }
+            header = Some((variables, count));
+        }
+        if !tokens.is_empty() && tokens[0] != "p" && tokens[0] != "c" {
+            for tok in tokens {
+                let declared = header.map(|(variables, _)| variables);
+                let lit = parse_literal(tok, i + 1, declared)?;
+                if lit == 0 {
+                    clauses.push(Clause::new(Vec::new()));
+                } else {
+                    let var = u32::try_from(lit.unsigned_abs()).map_err(|_| {
+                        ParseError::VariableOutOfRange {
+                            line: i + 1,
+                            variable: lit.unsigned_abs().to_string(),
+                            declared,
+                        }
+                    })?;
+                    clauses
+                        .last_mut()
+                        .unwrap()
+                        .literals
+                        .push(Literal::new(var, lit < 0));
+                }
+            }
+        }
+    }
+
+    if clauses.last().unwrap().literals.is_empty() {
+        clauses.pop();
+    }
+
+    if let Some((variables, count)) = header {
+        let found: HashSet<u32> = clauses
+            .iter()
+            .flat_map(|clause| clause.literals.iter().map(|literal| literal.value))
+            .collect();
+        if found.len() > variables {
+            return Err(ParseError::VariableCount {
+                declared: variables,
+                found: found.len(),
+            });
+        }
+        if clauses.len() != count {
+            return Err(ParseError::ClauseCount {
+                declared: count,
+                found: clauses.len(),
+            });
+        }
+    }
+    Ok((clauses, header.map(|(variables, _)| variables)))
+}
+
+// One clause per line, as identifiers negated by a leading `-` or `!`, e.g.
+// `a !b c`. Blank lines and anything after a `#` are ignored.
+pub fn parse_text_cnf(content: &str) -> Result<Formula, Error> {
+    let mut clauses: Vec<Clause> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut literals: Vec<Literal> = Vec::new();
+        for token in line.split_whitespace() {
+            let (name, negation) = match token.strip_prefix(['-', '!']) {
+                Some(name) => (name, true),
+                None => (token, false),
+            };
+            if name.is_empty() || name.starts_with(['-', '!']) {
+                return Err(ParseError::InvalidLiteral {
+                    line: i + 1,
+                    token: token.to_string(),
+                }
+                .into());
+            }
+            literals.push(Literal::new(name.to_string(), negation));
+        }
+        if !literals.is_empty() {
+            clauses.push(Clause::new(literals));
+        }
+    }
+    Ok(Formula::new(clauses))
+}
+
+// Reads `p wcnf N M top` files, one weighted clause per line. Clauses weighted
+// `top` or more, or written with an `h` weight, are hard; without a header
+// every numeric weight is soft.
+pub fn parse_wcnf(content: &str) -> Result<WeightedFormula, Error> {
+    let mut formula = WeightedFormula {
+        hard: Vec::new(),
+        soft: Vec::new(),
+    };
+    let mut top = u64::MAX;
+
+    for (i, line) in content.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.is_empty() || tokens[0] == "c" {
+            continue;
+        }
+        if tokens[0] == "p" {
+            if let Some(weight) = tokens.get(4) {
+                top = parse_weight(weight, i + 1)?;
+            }
+            continue;
+        }
+
+        let mut literals: Vec<Literal> = Vec::new();
+        for tok in &tokens[1..] {
+            let lit = parse_literal(tok, i + 1, None)?;
+            if lit == 0 {
+                break;
+            }
+            literals.push(Literal::new(lit.unsigned_abs().to_string(), lit < 0));
+        }
+        let clause = Clause::new(literals);
+        if tokens[0] == "h" {
+            formula.hard.push(clause);
+            continue;
+        }
+        let weight = parse_weight(tokens[0], i + 1)?;
+        if weight >= top {
+            formula.hard.push(clause);
+        } else {
+            formula.soft.push((weight, clause));
+        }
+    }
+
+    Ok(formula)
+}
+
+// Variables are checked against `declared`, the count from the header, when
+// there is one. An integer too large for an i64 is out of range rather than
+// invalid.
+fn parse_literal(token: &str, line: usize, declared: Option<usize>) -> Result<i64, ParseError> {
+    let digits = token.strip_prefix(['-', '+']).unwrap_or(token);
+    let out_of_range = || ParseError::VariableOutOfRange {
+        line,
+        variable: digits.to_string(),
+        declared,
+    };
+    match token.parse::<i64>() {
+        Ok(lit) if declared.is_some_and(|n| lit.unsigned_abs() > n as u64) => Err(out_of_range()),
+        Ok(lit) => Ok(lit),
+        Err(_) if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            Err(out_of_range())
+        }
+        Err(_) => Err(ParseError::InvalidLiteral {
+            line,
+            token: token.to_string(),
+        }),
+    }
+}
+
+fn parse_weight(token: &str, line: usize) -> Result<u64, ParseError> {
+    token.parse::<u64>().map_err(|_| ParseError::InvalidWeight {
+        line,
+        weight: token.to_string(),
+    })
+}
+
+// Splits files holding several problems, each starting at its own `p cnf`
+// header, and parses them separately.
+pub fn parse_dimacs_problems(content: &str) -> Result<Vec<DimacsCnf>, Error> {
+    let mut problems: Vec<String> = vec![String::new()];
+    let mut seen_header = false;
+
+    for line in content.lines() {
+        if line.split_whitespace().next() == Some("p") {
+            if seen_header {
+                problems.push(String::new());
+            }
+            seen_header = true;
+        }
+        let problem = problems.last_mut().unwrap();
+        problem.push_str(line);
+        problem.push('\n');
+    }
+
+    problems
+        .iter()
+        .map(|problem| parse_dimacs_cnf_with_warnings(problem))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn satlib_trailers_end_the_input() {
+        let cnf = parse_dimacs_cnf_with_warnings("p cnf 2 2\n1 -2 0\n2 0\n%\n0\n\n").unwrap();
+        assert_eq!(cnf.formula.clauses.len(), 2);
+        assert_eq!(cnf.warnings, ["ignored everything from the `%` on line 4"]);
+    }
+
+    #[test]
+    fn large_variables_are_read_or_rejected() {
+        // Past i32::MAX, but still a u32.
+        let formula = parse_dimacs_cnf("1 -2147483648 0\n").unwrap();
+        assert!(formula.variables.contains(&2147483648));
+
+        let out_of_range = |cnf: &str, line: usize, variable: &str, declared: Option<usize>| {
+            let error = parse_dimacs_cnf(cnf).err().unwrap();
+            assert_eq!(
+                error,
+                Error::Parse(ParseError::VariableOutOfRange {
+                    line,
+                    variable: variable.to_string(),
+                    declared,
+                })
+            );
+            error.to_string()
+        };
+        assert_eq!(
+            out_of_range("p cnf 3 1\n1 2147483648 0\n", 2, "2147483648", Some(3)),
+            "parse error: variable 2147483648 on line 2 is above the 3 declared in the header"
+        );
+        assert_eq!(
+            out_of_range("1 -4294967296 0\n", 1, "4294967296", None),
+            "parse error: variable 4294967296 on line 1 is too large"
+        );
+        out_of_range(
+            "1 99999999999999999999 0\n",
+            1,
+            "99999999999999999999",
+            None,
+        );
+    }
+
+    #[test]
+    fn wcnf_weights_split_hard_and_soft() {
+        let shown = |formula: &WeightedFormula| {
+            let hard: Vec<String> = formula.hard.iter().map(|c| c.to_string()).collect();
+            let soft: Vec<(u64, String)> = formula
+                .soft
+                .iter()
+                .map(|(weight, c)| (*weight, c.to_string()))
+                .collect();
+            (hard, soft)
+        };
+        let formula =
+            parse_wcnf("c comment\np wcnf 2 4 10\n10 1 2 0\n3 -1 0\n11 -2 0\nh 1 -2 0\n").unwrap();
+        let (hard, soft) = shown(&formula);
+        assert_eq!(hard, ["1 ∨ 2", "¬2", "1 ∨ ¬2"]);
+        assert_eq!(soft, [(3, String::from("¬1"))]);
+
+        // Without a header there is no top, so every weight is soft.
+        let (hard, soft) = shown(&parse_wcnf("10 1 0\nh -1 0\n").unwrap());
+        assert_eq!(hard, ["¬1"]);
+        assert_eq!(soft, [(10, String::from("1"))]);
+
+        assert_eq!(
+            parse_wcnf("p wcnf 1 1 10\nx 1 0\n").err(),
+            Some(Error::Parse(ParseError::InvalidWeight {
+                line: 2,
+                weight: String::from("x"),
+            }))
+        );
+        assert_eq!(
+            parse_wcnf("p wcnf 1 1 ten\n").err(),
+            Some(Error::Parse(ParseError::InvalidWeight {
+                line: 1,
+                weight: String::from("ten"),
+            }))
+        );
+    }
+
+    #[test]
+    fn wcnf_weights_at_the_edges() {
+        assert_eq!(
+            parse_wcnf("p wcnf 1 1 10\n-3 1 0\n").err(),
+            Some(Error::Parse(ParseError::InvalidWeight {
+                line: 2,
+                weight: String::from("-3"),
+            }))
+        );
+        // A weight of zero is soft, and one of exactly top is hard.
+        let formula = parse_wcnf("p wcnf 1 2 10\n0 1 0\n10 -1 0\n").unwrap();
+        assert_eq!(formula.soft.len(), 1);
+        assert_eq!(formula.soft[0].0, 0);
+        assert_eq!(formula.hard.len(), 1);
+        assert_eq!(formula.hard[0].to_string(), "¬1");
+    }
+
+    #[test]
+    fn files_can_hold_several_problems() {
+        let problems =
+            parse_dimacs_problems("c first\np cnf 2 2\n1 2 0\n-1 0\np cnf 3 1\n1 -2 3 0\n")
+                .unwrap();
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].formula.clauses.len(), 2);
+        assert_eq!(problems[0].formula.variables.len(), 2);
+        assert_eq!(problems[1].formula.clauses.len(), 1);
+        assert_eq!(problems[1].formula.variables.len(), 3);
+
+        let single = parse_dimacs_problems("p cnf 1 1\n1 0\n").unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].formula.clauses.len(), 1);
+    }
+
+    #[test]
+    fn headers_are_checked_and_declare_variables() {
+        assert_eq!(
+            parse_dimacs_cnf("p cnf 2 1\n1 2 3 0\n").err(),
+            Some(Error::Parse(ParseError::VariableOutOfRange {
+                line: 2,
+                variable: String::from("3"),
+                declared: Some(2),
+            }))
+        );
+        assert_eq!(
+            parse_dimacs_cnf("p cnf 2 2\n1 0\n").err(),
+            Some(Error::Parse(ParseError::ClauseCount {
+                declared: 2,
+                found: 1,
+            }))
+        );
+        assert_eq!(
+            // Clauses before the header cannot be checked as they are read.
+            parse_dimacs_cnf("1 2 3 0\np cnf 2 1\n").err(),
+            Some(Error::Parse(ParseError::VariableCount {
+                declared: 2,
+                found: 3,
+            }))
+        );
+        assert_eq!(
+            parse_dimacs_cnf("p cnf 2\n1 0\n").err(),
+            Some(Error::Parse(ParseError::InvalidHeader { line: 1 }))
+        );
+
+        // Declared variables no clause uses still get a value.
+        let formula = parse_dimacs_cnf("p cnf 5 2\n1 -2 0\n2 3 0\n").unwrap();
+        let mut variables: Vec<u32> = formula.variables.iter().copied().collect();
+        variables.sort();
+        assert_eq!(variables, [1, 2, 3, 4, 5]);
+        let mut solver = CdclSolver::new(formula);
+        solver.solve();
+        let model = solver.model().unwrap();
+        assert!((1..=5).all(|variable| model.values.contains_key(&variable)));
+
+        let cnf = parse_dimacs_cnf_with_warnings("1 0\n").unwrap();
+        assert_eq!(cnf.warnings, ["no `p cnf` header, counts not checked"]);
+    }
+
+    #[test]
+    fn text_cnf_reads_negations_and_comments() {
+        let formula = parse_text_cnf(
+            "# (a ∨ ¬b ∨ c) ∧ (b ∨ ¬c)\na !b c\n\nb -c   # trailing comment\n  # indented comment\nnaïve -x_1\n",
+        )
+        .unwrap();
+        let clauses: Vec<String> = formula.clauses.iter().map(|c| c.to_string()).collect();
+        assert_eq!(clauses, ["a ∨ ¬b ∨ c", "b ∨ ¬c", "naïve ∨ ¬x_1"]);
+        assert_eq!(formula.variables.len(), 5);
+
+        assert_eq!(
+            parse_text_cnf("a\n!\n").err(),
+            Some(Error::Parse(ParseError::InvalidLiteral {
+                line: 2,
+                token: String::from("!"),
+            }))
+        );
+    }
+}
@@ -1,77 +1,224 @@
-use solver::CdclSolver;
-use solver::SolverResult;
+use cdcl::config::BranchingHeuristic;
+use cdcl::config::SolverConfig;
+use cdcl::config::Verbosity;
+use cdcl::model;
+use cdcl::parse_dimacs_problems;
+use cdcl::parse_text_cnf;
+use cdcl::wff::Variable;
+use cdcl::CdclSolver;
+use cdcl::Formula;
+use cdcl::SolverResult;
 use std::env;
 use std::fs;
+use std::io;
+use std::io::Write;
 use std::process;
-use wff::Clause;
-use wff::Formula;
-use wff::Literal;
+use std::time::Instant;
 
-mod solver;
-mod tseitin;
-mod wff;
+// With `dimacs_model` only the `s` and `v` lines go to stdout, for
+// verifiers; everything else is logged to stderr.
+fn solve_and_print<V: Variable>(formula: Formula<V>, config: SolverConfig, dimacs_model: bool) {
+    let mut solver = CdclSolver::with_config(formula, config);
+    let mut log = log_output(dimacs_model);
+    if dimacs_model {
+        solver.set_output(log_output(dimacs_model));
+    }
+    if let Some(chain) = solver.level_zero_conflict() {
+        writeln!(log, "Formula is UNSAT by unit propagation through:").unwrap();
+        for clause in &chain {
+            writeln!(log, "{}", clause).unwrap();
+        }
+        if dimacs_model {
+            print!("{}", model::to_dimacs_model(&solver));
+        }
+        return;
+    }
 
-pub fn parse_dimacs_cnf(content: &str) -> Formula {
-    let mut clauses = vec![Clause::new(Vec::new())];
+    let start = Instant::now();
+    solver.solve();
+    let elapsed = start.elapsed();
+    let result = solver.sat();
+    if matches!(result, SolverResult::Satisfied) && !solver.verify_model() {
+        eprintln!("Error: the model found does not satisfy the formula.");
+        process::exit(1);
+    }
 
-    for line in content.lines() {
-        let tokens: Vec<&str> = line.split_whitespace().collect();
-        if !tokens.is_empty() && tokens[0] != "p" && tokens[0] != "c" {
-            for tok in tokens {
-                if let Ok(lit) = tok.parse::<i32>() {
-                    if lit == 0 {
-                        clauses.push(Clause::new(Vec::new()));
-                    } else {
-                        let var = lit.abs().to_string();
-                        let neg = lit < 0;
-                        clauses
-                            .last_mut()
-                            .unwrap()
-                            .literals
-                            .push(Literal::new(var, neg));
+    match result {
+        _ if dimacs_model => print!("{}", model::to_dimacs_model(&solver)),
+        SolverResult::Satisfied => {
+            println!("Formula is SAT with assignments:");
+            // Variables the search never needed are filled in by the free
+            // variable policy, and marked as such.
+            let model = solver.model().unwrap();
+            let mut variables: Vec<&V> = model.values.keys().chain(&model.free).collect();
+            variables.sort_by_key(|variable| variable.key());
+            for var in variables {
+                match model.values.get(var) {
+                    Some(value) if solver.assignments().get(var).is_some() => {
+                        println!("{}: {}", var, value)
                     }
+                    Some(value) => println!("{}: {} (free)", var, value),
+                    None => println!("{}: (free)", var),
                 }
             }
         }
+        SolverResult::Unresolved => {
+            println!("Formula is UNSAT.");
+        }
     }
 
-    if clauses.last().unwrap().literals.is_empty() {
-        clauses.pop();
+    if solver.config().verbosity == Verbosity::Silent {
+        return;
     }
+    writeln!(log, "{}", solver.statistics()).unwrap();
+    let (conflicts_per_second, propagations_per_second) = solver.statistics().rates(elapsed);
+    writeln!(
+        log,
+        "Solved in {:.3}s ({:.0} conflicts/s, {:.0} propagations/s).",
+        elapsed.as_secs_f64(),
+        conflicts_per_second,
+        propagations_per_second
+    )
+    .unwrap();
+}
 
-    Formula::new(clauses)
+fn log_output(dimacs_model: bool) -> Box<dyn Write + Send + Sync> {
+    if dimacs_model {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
 }
 
 pub fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Provide one DIMACS CNF filename as argument.");
-        process::exit(1);
+    let mut filename: Option<&String> = None;
+    let mut config = SolverConfig::default()
+        .branching(BranchingHeuristic::Vsids)
+        .verbosity(Verbosity::Trace);
+    let mut dimacs_model = false;
+    let mut text_format = false;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--heuristic" => {
+                i += 1;
+                match args.get(i).map(|arg| arg.parse::<BranchingHeuristic>()) {
+                    Some(Ok(heuristic)) => config = config.branching(heuristic),
+                    _ => {
+                        eprintln!("--heuristic expects one of random, first, jw, dlis, vsids, static:<v1,v2,...>.");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--seed" => {
+                i += 1;
+                match args.get(i).map(|arg| arg.parse::<u64>()) {
+                    Some(Ok(seed)) => config = config.seed(Some(seed)),
+                    _ => {
+                        eprintln!("--seed expects a non-negative integer.");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--watch-threshold" => {
+                i += 1;
+                match args.get(i).map(|arg| arg.parse::<usize>()) {
+                    Some(Ok(threshold)) => config = config.watch_threshold(Some(threshold)),
+                    _ => {
+                        eprintln!("--watch-threshold expects a non-negative integer.");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--verbosity" => {
+                i += 1;
+                match args.get(i).map(|arg| arg.parse::<Verbosity>()) {
+                    Some(Ok(verbosity)) => config = config.verbosity(verbosity),
+                    _ => {
+                        eprintln!("--verbosity expects one of silent, info, trace.");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--pure-literals" => config = config.pure_literals(true),
+            "--dimacs-model" => dimacs_model = true,
+            "--format" => {
+                i += 1;
+                match args.get(i).map(String::as_str) {
+                    Some("dimacs") => text_format = false,
+                    Some("text") => text_format = true,
+                    _ => {
+                        eprintln!("--format expects one of dimacs, text.");
+                        process::exit(1);
+                    }
+                }
+            }
+            _ if filename.is_none() => filename = Some(&args[i]),
+            _ => {
+                eprintln!("Provide one DIMACS CNF filename as argument.");
+                process::exit(1);
+            }
+        }
+        i += 1;
     }
 
-    let filename = &args[1];
-    let dimacs_cnf = match fs::read_to_string(filename) {
+    // Without a filename, or with `-`, the CNF is read from stdin.
+    let (name, content) = match filename.map(String::as_str) {
+        None | Some("-") => ("stdin", io::read_to_string(io::stdin())),
+        Some(filename) => (filename, fs::read_to_string(filename)),
+    };
+    let dimacs_cnf = match content {
         Ok(content) => content,
         Err(e) => {
-            eprintln!("Error reading file: {}", e);
+            eprintln!("Error reading {}: {}", name, e);
             process::exit(1);
         }
     };
 
-    let formula = parse_dimacs_cnf(&dimacs_cnf);
-    let mut solver = CdclSolver::new(formula);
-    solver.solve();
-    let result = solver.sat();
+    // Text CNF names its variables; DIMACS ones stay numbers.
+    let parsed = if text_format {
+        parse_text_cnf(&dimacs_cnf)
+            .map(|formula| solve_all(vec![(formula, Vec::new())], &config, dimacs_model))
+    } else {
+        parse_dimacs_problems(&dimacs_cnf).map(|problems| {
+            let problems = problems
+                .into_iter()
+                .map(|problem| (problem.formula, problem.warnings))
+                .collect();
+            solve_all(problems, &config, dimacs_model)
+        })
+    };
+    if let Err(e) = parsed {
+        eprintln!("Error in {}: {}", name, e);
+        process::exit(1);
+    }
+}
 
-    match result {
-        SolverResult::Satisfied => {
-            println!("Formula is SAT with assignments:");
-            for (var, assignment) in solver.assignments().assignments.iter() {
-                println!("{}: {}", var, assignment.value);
-            }
+// Solves each formula in turn, after printing the warnings its input gave.
+fn solve_all<V: Variable>(
+    problems: Vec<(Formula<V>, Vec<String>)>,
+    config: &SolverConfig,
+    dimacs_model: bool,
+) {
+    let mut log = log_output(dimacs_model);
+    if config.verbosity > Verbosity::Silent {
+        writeln!(log, "Using the {} branching heuristic.", config.branching).unwrap();
+    }
+    let count = problems.len();
+    for (i, (problem, warnings)) in problems.into_iter().enumerate() {
+        if count > 1 {
+            writeln!(log, "Problem {}:", i + 1).unwrap();
         }
-        SolverResult::Unresolved => {
-            println!("Formula is UNSAT.");
+        for warning in &warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        let (mut formula, tautologies) = Formula::simplified(problem.clauses);
+        formula.variables.extend(problem.variables);
+        if tautologies > 0 {
+            eprintln!("Warning: dropped {} tautological clauses", tautologies);
         }
+        solve_and_print(formula, config.clone(), dimacs_model);
     }
 }
@@ -0,0 +1,271 @@
+use crate::collections::BTreeMap;
+use crate::collections::HashMap;
+use crate::config::SolverConfig;
+use crate::config::Verbosity;
+use crate::solver::CdclSolver;
+use crate::wff::Clause;
+use crate::wff::Formula;
+use crate::wff::Literal;
+use crate::wff::WeightedFormula;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub struct MaxSatResult {
+    // The least total weight of violated soft clauses.
+    pub cost: u64,
+    pub model: HashMap<String, bool>,
+}
+
+pub struct LexicographicResult {
+    // The optimal cost of each soft group, in priority order.
+    pub costs: Vec<u64>,
+    pub model: HashMap<String, bool>,
+}
+
+// Satisfies `formula` while minimizing the weight of the violated soft
+// clauses of group 0, then of group 1 without giving up group 0's optimum,
+// and so on. Each soft clause gets a relaxation variable, and every
+// improvement is asked for by bounding the weight of the relaxed clauses one
+// below the best cost found so far, until that is UNSAT. None when the hard
+// clauses are UNSAT.
+pub fn solve_lexicographic_maxsat(
+    formula: &Formula,
+    soft_groups: Vec<Vec<(Clause, u64)>>,
+) -> Option<LexicographicResult> {
+    let mut clauses = formula.clauses.clone();
+    let mut relaxations: Vec<Vec<(Literal, u64)>> = Vec::new();
+    for (g, group) in soft_groups.iter().enumerate() {
+        let mut relaxed: Vec<(Literal, u64)> = Vec::new();
+        for (i, (clause, weight)) in group.iter().enumerate() {
+            let relaxation = Literal::new(format!("maxsat_r_{}_{}", g, i), false);
+            let mut literals = clause.literals.clone();
+            literals.push(relaxation.clone());
+            clauses.push(Clause::new(literals));
+            relaxed.push((relaxation, *weight));
+        }
+        relaxations.push(relaxed);
+    }
+
+    let mut model = solve(clauses.clone())?;
+    let mut costs: Vec<u64> = Vec::new();
+    for (g, group) in soft_groups.iter().enumerate() {
+        let mut cost = violated_weight(group, &model);
+        let mut attempt = 0;
+        while cost > 0 {
+            attempt += 1;
+            let prefix = format!("maxsat_s_{}_{}", g, attempt);
+            let mut bounded = clauses.clone();
+            bounded.extend(at_most(&relaxations[g], cost - 1, &prefix));
+            match solve(bounded) {
+                Some(better) => {
+                    cost = violated_weight(group, &better);
+                    model = better;
+                }
+                None => break,
+            }
+        }
+        // Later groups are optimized without giving this cost up.
+        clauses.extend(at_most(&relaxations[g], cost, &format!("maxsat_s_{}", g)));
+        costs.push(cost);
+    }
+
+    model.retain(|variable, _| !variable.starts_with("maxsat_"));
+    Some(LexicographicResult { costs, model })
+}
+
+// Satisfies the hard clauses of `formula` while minimizing the weight of the
+// violated soft ones, by the linear search of `solve_lexicographic_maxsat`
+// with a single group. None when the hard clauses are UNSAT.
+pub fn solve_maxsat(formula: &WeightedFormula) -> Option<MaxSatResult> {
+    let soft = formula
+        .soft
+        .iter()
+        .map(|(weight, clause)| (clause.clone(), *weight))
+        .collect();
+    let result = solve_lexicographic_maxsat(&Formula::new(formula.hard.clone()), vec![soft])?;
+    Some(MaxSatResult {
+        cost: result.costs[0],
+        model: result.model,
+    })
+}
+
+fn solve(clauses: Vec<Clause>) -> Option<HashMap<String, bool>> {
+    let config = SolverConfig::default().verbosity(Verbosity::Silent);
+    let mut solver = CdclSolver::with_config(Formula::new(clauses), config);
+    solver.solve();
+    solver.model().map(|model| model.values)
+}
+
+fn violated_weight(group: &[(Clause, u64)], model: &HashMap<String, bool>) -> u64 {
+    group
+        .iter()
+        .filter(|(clause, _)| {
+            !clause
+                .literals
+                .iter()
+                .any(|l| model.get(&l.value) == Some(&!l.negation))
+        })
+        .map(|(_, weight)| weight)
+        .sum()
+}
+
+// Generalized totalizer: a balanced tree over the weighted literals whose
+// node outputs `{prefix}_{node}_{sum}` hold when the true literals below the
+// node weigh at least `sum`. A node only has outputs for the sums its
+// subtree can reach, so the encoding grows with the number of distinct
+// sums and not with the weight values. Sums over `bound` are forbidden where
+// they first appear.
+fn at_most(literals: &[(Literal, u64)], bound: u64, prefix: &str) -> Vec<Clause> {
+    let mut clauses: Vec<Clause> = Vec::new();
+    let mut nodes = 0;
+    totalizer(literals, bound, prefix, &mut nodes, &mut clauses);
+    clauses
+}
+
+// The outputs of the totalizer node over `literals`, by sum.
+fn totalizer(
+    literals: &[(Literal, u64)],
+    bound: u64,
+    prefix: &str,
+    nodes: &mut usize,
+    clauses: &mut Vec<Clause>,
+) -> BTreeMap<u64, Literal> {
+    let mut outputs: BTreeMap<u64, Literal> = BTreeMap::new();
+    if let [(x, weight)] = literals {
+        if *weight > bound {
+            clauses.push(Clause::new(vec![x.negate()]));
+        } else if *weight > 0 {
+            outputs.insert(*weight, x.clone());
+        }
+        return outputs;
+    }
+    if literals.is_empty() {
+        return outputs;
+    }
+    let (left, right) = literals.split_at(literals.len() / 2);
+    let left = totalizer(left, bound, prefix, nodes, clauses);
+    let right = totalizer(right, bound, prefix, nodes, clauses);
+    *nodes += 1;
+    let node = *nodes;
+    let mut output = |sum: u64| {
+        outputs
+            .entry(sum)
+            .or_insert_with(|| Literal::new(format!("{}_{}_{}", prefix, node, sum), false))
+            .clone()
+    };
+    for (sum, a) in left.iter().chain(right.iter()) {
+        clauses.push(Clause::new(vec![a.negate(), output(*sum)]));
+    }
+    for (a_sum, a) in left.iter() {
+        for (b_sum, b) in right.iter() {
+            if a_sum + b_sum > bound {
+                clauses.push(Clause::new(vec![a.negate(), b.negate()]));
+            } else {
+                clauses.push(Clause::new(vec![
+                    a.negate(),
+                    b.negate(),
+                    output(a_sum + b_sum),
+                ]));
+            }
+        }
+    }
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn clause(literals: &[(&str, bool)]) -> Clause {
+        Clause::new(
+            literals
+                .iter()
+                .map(|(name, negation)| Literal::new(name.to_string(), *negation))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn large_weights_are_solved() {
+        let formula =
+            crate::parse_wcnf("p wcnf 2 3 1000000\n1000000 1 2 0\n300000 -1 0\n400000 -2 0\n")
+                .unwrap();
+        let result = solve_maxsat(&formula).unwrap();
+        assert_eq!(result.cost, 300000);
+        assert_eq!(result.model.get("1"), Some(&true));
+        assert_eq!(result.model.get("2"), Some(&false));
+    }
+
+    #[test]
+    fn cost_is_the_least_violated_weight() {
+        let mut rng = StdRng::seed_from_u64(283);
+        for _ in 0..50 {
+            let names = ["a", "b", "c", "d"];
+            let random_clause = |rng: &mut StdRng| {
+                let literals: Vec<(&str, bool)> = (0..rng.gen_range(1..3))
+                    .map(|_| (names[rng.gen_range(0..4)], rng.gen()))
+                    .collect();
+                clause(&literals)
+            };
+            let hard: Vec<Clause> = (0..2).map(|_| random_clause(&mut rng)).collect();
+            let soft: Vec<(u64, Clause)> = (0..6)
+                .map(|_| (rng.gen_range(0..100000), random_clause(&mut rng)))
+                .collect();
+            let group: Vec<(Clause, u64)> = soft.iter().map(|(w, c)| (c.clone(), *w)).collect();
+            let satisfied = |clause: &Clause, model: &HashMap<String, bool>| {
+                clause
+                    .literals
+                    .iter()
+                    .any(|l| model.get(&l.value) == Some(&!l.negation))
+            };
+            let best = (0..1 << names.len())
+                .map(|bits: u32| {
+                    names
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| (name.to_string(), bits >> i & 1 == 1))
+                        .collect::<HashMap<String, bool>>()
+                })
+                .filter(|model| hard.iter().all(|clause| satisfied(clause, model)))
+                .map(|model| violated_weight(&group, &model))
+                .min();
+
+            let formula = WeightedFormula {
+                hard: hard.clone(),
+                soft: soft.clone(),
+            };
+            let result = solve_maxsat(&formula);
+            assert_eq!(result.as_ref().map(|result| result.cost), best);
+            if let Some(result) = result {
+                assert!(hard.iter().all(|clause| satisfied(clause, &result.model)));
+                assert_eq!(violated_weight(&group, &result.model), result.cost);
+            }
+        }
+    }
+
+    #[test]
+    fn earlier_groups_come_first() {
+        // Weighted together, violating `!a` is cheaper; by priority it must
+        // be kept, at the price of `!b`.
+        let formula = Formula::new(vec![clause(&[("a", false), ("b", false)])]);
+        let groups = vec![
+            vec![(clause(&[("a", true)]), 1)],
+            vec![(clause(&[("b", true)]), 10)],
+        ];
+        let result = solve_lexicographic_maxsat(&formula, groups.clone()).unwrap();
+        assert_eq!(result.costs, vec![0, 10]);
+        assert_eq!(result.model.get("a"), Some(&false));
+        assert_eq!(result.model.get("b"), Some(&true));
+
+        let weighted = solve_lexicographic_maxsat(&formula, vec![groups.concat()]).unwrap();
+        assert_eq!(weighted.costs, vec![1]);
+        assert_eq!(weighted.model.get("a"), Some(&true));
+        assert_eq!(weighted.model.get("b"), Some(&false));
+    }
+}
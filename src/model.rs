@@ -0,0 +1,144 @@
+use crate::collections::HashMap;
+use crate::collections::HashSet;
+use crate::proof;
+use crate::solver::CdclSolver;
+use crate::wff::Variable;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// A model of a formula. `free` holds the variables the search left
+// unassigned when the free variable policy is `Mark`, and is empty otherwise.
+pub struct Model<V = String> {
+    pub values: HashMap<V, bool>,
+    pub free: HashSet<V>,
+}
+
+// Variables assigned in both models but to different values, with their
+// value in `a` and then in `b`, in variable order.
+pub fn model_diff<V: Variable>(a: &HashMap<V, bool>, b: &HashMap<V, bool>) -> Vec<(V, bool, bool)> {
+    let mut diff: Vec<(V, bool, bool)> = a
+        .iter()
+        .filter_map(|(variable, value)| match b.get(variable) {
+            Some(other) if other != value => Some((variable.clone(), *value, *other)),
+            _ => None,
+        })
+        .collect();
+    diff.sort_by(|x, y| x.0.key().cmp(&y.0.key()));
+    diff
+}
+
+// Variables `a` assigns but `b` does not, with their value in `a`, in
+// variable order. Swap the arguments for those only `b` assigns.
+pub fn unshared_variables<V: Variable>(
+    a: &HashMap<V, bool>,
+    b: &HashMap<V, bool>,
+) -> Vec<(V, bool)> {
+    let mut only: Vec<(V, bool)> = a
+        .iter()
+        .filter(|(variable, _)| !b.contains_key(*variable))
+        .map(|(variable, value)| (variable.clone(), *value))
+        .collect();
+    only.sort_by(|x, y| x.0.key().cmp(&y.0.key()));
+    only
+}
+
+// The result in SAT competition format: an `s` line, and for a SAT formula a
+// `v` line of signed literals ending in 0. Variables are numbered as in
+// proofs, so names that are not integers follow the largest integer one.
+// Variables the search left unassigned follow the free variable policy, and
+// are left off the line when it leaves them out of the model.
+pub fn to_dimacs_model<V: Variable>(solver: &CdclSolver<V>) -> String {
+    let model = match solver.model() {
+        Some(model) => model,
+        None => return String::from("s UNSATISFIABLE\n"),
+    };
+    let ids = proof::variable_ids(&solver.formula.variables);
+    let mut literals: Vec<i64> = model
+        .values
+        .iter()
+        .map(|(variable, value)| {
+            let id = ids[variable] as i64;
+            if *value {
+                id
+            } else {
+                -id
+            }
+        })
+        .collect();
+    literals.sort_by_key(|literal| literal.abs());
+    let mut line = String::from("v");
+    for literal in literals {
+        line.push_str(&format!(" {}", literal));
+    }
+    format!("s SATISFIABLE\n{} 0\n", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FreePolicy;
+    use crate::config::SolverConfig;
+    use crate::solver::sink;
+
+    fn dimacs_model(policy: FreePolicy) -> String {
+        let formula = crate::parse_dimacs_cnf("p cnf 2 1\n1 0\n").unwrap();
+        assert!(formula.variables.contains(&2));
+        let config = SolverConfig::default().free_variable_policy(policy);
+        let mut solver = CdclSolver::with_config(formula, config);
+        solver.set_output(sink());
+        assert!(solver.is_satisfiable());
+        to_dimacs_model(&solver)
+    }
+
+    #[test]
+    fn dimacs_model_follows_the_free_variable_policy() {
+        assert!(dimacs_model(FreePolicy::AllTrue).ends_with("v 1 2 0\n"));
+        assert!(dimacs_model(FreePolicy::AllFalse).ends_with("v 1 -2 0\n"));
+        assert!(dimacs_model(FreePolicy::Omit).ends_with("v 1 0\n"));
+        assert!(dimacs_model(FreePolicy::Mark).ends_with("v 1 0\n"));
+    }
+
+    #[test]
+    fn models_are_compared_in_variable_order() {
+        let model = |values: &[(&str, bool)]| -> HashMap<String, bool> {
+            values
+                .iter()
+                .map(|(variable, value)| (String::from(*variable), *value))
+                .collect()
+        };
+        let a = model(&[
+            ("10", true),
+            ("2", false),
+            ("b", true),
+            ("x", false),
+            ("a", true),
+        ]);
+        let b = model(&[
+            ("10", false),
+            ("2", true),
+            ("b", true),
+            ("y", true),
+            ("a", false),
+        ]);
+        let owned = |diff: &[(&str, bool, bool)]| -> Vec<(String, bool, bool)> {
+            diff.iter()
+                .map(|(variable, x, y)| (String::from(*variable), *x, *y))
+                .collect()
+        };
+        assert_eq!(
+            model_diff(&a, &b),
+            owned(&[("2", false, true), ("10", true, false), ("a", true, false)])
+        );
+        assert_eq!(
+            model_diff(&b, &a),
+            owned(&[("2", true, false), ("10", false, true), ("a", false, true)])
+        );
+        assert!(model_diff(&a, &a).is_empty());
+
+        assert_eq!(unshared_variables(&a, &b), [(String::from("x"), false)]);
+        assert_eq!(unshared_variables(&b, &a), [(String::from("y"), true)]);
+        assert!(unshared_variables(&a, &a).is_empty());
+        assert_eq!(unshared_variables(&a, &HashMap::new()).len(), a.len());
+    }
+}
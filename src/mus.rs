@@ -0,0 +1,122 @@
+use crate::config::SolverConfig;
+use crate::config::Verbosity;
+use crate::solver::CdclSolver;
+use crate::wff::Clause;
+use crate::wff::Formula;
+use crate::wff::Literal;
+use crate::wff::Variable;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+// Enumerates every minimal unsatisfiable subset of the clauses (MARCO).
+//
+// A map formula over one variable per clause tracks the subsets not yet
+// explored. Each seed drawn from it is either grown into a maximal satisfiable
+// subset, whose subsets are then blocked, or shrunk into a MUS, whose supersets
+// are then blocked. A formula can have exponentially many MUSes in its number
+// of clauses, and every step issues SAT calls, so this is for small inputs.
+pub fn all_muses<V: Variable>(formula: &Formula<V>) -> Vec<Vec<Clause<V>>> {
+    let n = formula.clauses.len();
+    let mut map: Vec<Clause> = Vec::new();
+    let mut muses: Vec<Vec<Clause<V>>> = Vec::new();
+
+    loop {
+        let mut map_solver = silent_solver(map.clone());
+        if !map_solver.is_satisfiable() {
+            break;
+        }
+        // Clauses the map leaves unconstrained are included in the seed.
+        let seed: Vec<usize> = (0..n)
+            .filter(|i| map_solver.value_of(&i.to_string()) != Some(false))
+            .collect();
+
+        if is_satisfiable(formula, &seed) {
+            let mss = grow(formula, seed);
+            map.push(Clause::new(
+                (0..n)
+                    .filter(|i| !mss.contains(i))
+                    .map(|i| Literal::new(i.to_string(), false))
+                    .collect(),
+            ));
+        } else {
+            let mus = shrink(formula, seed);
+            map.push(Clause::new(
+                mus.iter()
+                    .map(|i| Literal::new(i.to_string(), true))
+                    .collect(),
+            ));
+            muses.push(mus.iter().map(|i| formula.clauses[*i].clone()).collect());
+        }
+    }
+    muses
+}
+
+fn is_satisfiable<V: Variable>(formula: &Formula<V>, subset: &[usize]) -> bool {
+    let clauses = subset.iter().map(|i| formula.clauses[*i].clone()).collect();
+    silent_solver(clauses).is_satisfiable()
+}
+
+// The many solves behind one call stay out of the caller's output.
+fn silent_solver<V: Variable>(clauses: Vec<Clause<V>>) -> CdclSolver<V> {
+    let config = SolverConfig::default().verbosity(Verbosity::Silent);
+    CdclSolver::with_config(Formula::new(clauses), config)
+}
+
+fn grow<V: Variable>(formula: &Formula<V>, mut subset: Vec<usize>) -> Vec<usize> {
+    for i in 0..formula.clauses.len() {
+        if subset.contains(&i) {
+            continue;
+        }
+        subset.push(i);
+        if !is_satisfiable(formula, &subset) {
+            subset.pop();
+        }
+    }
+    subset
+}
+
+fn shrink<V: Variable>(formula: &Formula<V>, mut subset: Vec<usize>) -> Vec<usize> {
+    let mut i = 0;
+    while i < subset.len() {
+        let removed = subset.remove(i);
+        if is_satisfiable(formula, &subset) {
+            subset.insert(i, removed);
+            i += 1;
+        }
+    }
+    subset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::brute_force_sat;
+    use crate::solver::SolverResult;
+    use alloc::string::String;
+
+    #[test]
+    fn finds_every_mus() {
+        let formula = crate::parse_text_cnf("a\n-a\nb\n-b\n").unwrap();
+        let mut muses: Vec<Vec<String>> = all_muses(&formula)
+            .into_iter()
+            .map(|mus| {
+                let formula = Formula::new(mus.clone());
+                assert_eq!(brute_force_sat(&formula), SolverResult::Unresolved);
+                // Dropping any one clause leaves it satisfiable.
+                for i in 0..mus.len() {
+                    let mut rest = mus.clone();
+                    rest.remove(i);
+                    assert_eq!(
+                        brute_force_sat(&Formula::new(rest)),
+                        SolverResult::Satisfied
+                    );
+                }
+                let mut clauses: Vec<String> = mus.iter().map(|c| c.to_string()).collect();
+                clauses.sort();
+                clauses
+            })
+            .collect();
+        muses.sort();
+        assert_eq!(muses, [["a", "¬a"], ["b", "¬b"]]);
+    }
+}
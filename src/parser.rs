@@ -0,0 +1,305 @@
+use crate::error::Error;
+use crate::wff::Expr;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::error;
+use core::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedToken {
+        position: usize,
+        token: String,
+    },
+    UnexpectedEnd,
+    // A WCNF clause weight that is not a non-negative integer.
+    InvalidWeight {
+        line: usize,
+        weight: String,
+    },
+    // A DIMACS token that is not an integer literal.
+    InvalidLiteral {
+        line: usize,
+        token: String,
+    },
+    // A DIMACS variable above the count in the header, or too large to be
+    // read at all.
+    VariableOutOfRange {
+        line: usize,
+        variable: String,
+        declared: Option<usize>,
+    },
+    // A `p` line that is not `p cnf <variables> <clauses>`.
+    InvalidHeader {
+        line: usize,
+    },
+    // More distinct variables used than the header declares.
+    VariableCount {
+        declared: usize,
+        found: usize,
+    },
+    // A different number of clauses than the header declares.
+    ClauseCount {
+        declared: usize,
+        found: usize,
+    },
+    // A search tree line that is not a decision, or is indented deeper than
+    // one below the line before.
+    InvalidSearchNode {
+        line: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken { position, token } => {
+                write!(f, "unexpected '{}' at position {}", token, position)
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::InvalidWeight { line, weight } => {
+                write!(f, "invalid weight '{}' on line {}", weight, line)
+            }
+            ParseError::InvalidLiteral { line, token } => {
+                write!(f, "invalid literal '{}' on line {}", token, line)
+            }
+            ParseError::VariableOutOfRange {
+                line,
+                variable,
+                declared: Some(declared),
+            } => write!(
+                f,
+                "variable {} on line {} is above the {} declared in the header",
+                variable, line, declared
+            ),
+            ParseError::VariableOutOfRange {
+                line,
+                variable,
+                declared: None,
+            } => write!(f, "variable {} on line {} is too large", variable, line),
+            ParseError::InvalidHeader { line } => {
+                write!(f, "invalid problem line on line {}", line)
+            }
+            ParseError::VariableCount { declared, found } => write!(
+                f,
+                "header declares {} variables but {} are used",
+                declared, found
+            ),
+            ParseError::ClauseCount { declared, found } => write!(
+                f,
+                "header declares {} clauses but {} are given",
+                declared, found
+            ),
+            ParseError::InvalidSearchNode { line } => {
+                write!(f, "invalid search tree node on line {}", line)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Var(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+// Parses a formula written with ¬ ∧ ⊕ ∨ → ↔, listed from the tightest binding
+// to the loosest. → associates to the right, the other operators to the left.
+pub fn parse_expr(s: &str) -> Result<Expr, Error> {
+    let mut parser = Parser {
+        tokens: tokenize(s)?,
+        pos: 0,
+    };
+    let expr = parser.parse_iff()?;
+    match parser.tokens.get(parser.pos) {
+        Some((position, token)) => Err(unexpected(*position, token).into()),
+        None => Ok(expr),
+    }
+}
+
+// `parse_expr` under its older name.
+pub fn parse_wff(s: &str) -> Result<Expr, Error> {
+    parse_expr(s)
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut chars = s.chars().enumerate().peekable();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '¬' | '∧' | '⊕' | '∨' | '→' | '↔' => tokens.push((position, Token::Op(c))),
+            '(' => tokens.push((position, Token::Open)),
+            ')' => tokens.push((position, Token::Close)),
+            c if c.is_whitespace() => {}
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some((_, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || *c == '_') {
+                        break;
+                    }
+                    name.push(*c);
+                    chars.next();
+                }
+                tokens.push((position, Token::Var(name)));
+            }
+            c => {
+                return Err(ParseError::UnexpectedToken {
+                    position,
+                    token: c.to_string(),
+                })
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn unexpected(position: usize, token: &Token) -> ParseError {
+    let token = match token {
+        Token::Var(name) => name.clone(),
+        Token::Op(c) => c.to_string(),
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+    };
+    ParseError::UnexpectedToken { position, token }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, op: char) -> bool {
+        if let Some((_, Token::Op(c))) = self.tokens.get(self.pos) {
+            if *c == op {
+                self.pos += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn parse_iff(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_implies()?;
+        while self.eat('↔') {
+            left = Expr::iff(left, self.parse_implies()?);
+        }
+        Ok(left)
+    }
+
+    fn parse_implies(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_or()?;
+        if self.eat('→') {
+            return Ok(Expr::implies(left, self.parse_implies()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_xor()?;
+        while self.eat('∨') {
+            left = Expr::or(left, self.parse_xor()?);
+        }
+        Ok(left)
+    }
+
+    fn parse_xor(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_and()?;
+        while self.eat('⊕') {
+            left = Expr::xor(left, self.parse_and()?);
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_unary()?;
+        while self.eat('∧') {
+            left = Expr::and(left, self.parse_unary()?);
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        let (position, token) = match self.tokens.get(self.pos) {
+            Some((position, token)) => (*position, token.clone()),
+            None => return Err(ParseError::UnexpectedEnd),
+        };
+        self.pos += 1;
+        match token {
+            Token::Op('¬') => Ok(Expr::not(self.parse_unary()?)),
+            Token::Var(name) => Ok(Expr::Var(name)),
+            Token::Open => {
+                let expr = self.parse_iff()?;
+                match self.tokens.get(self.pos) {
+                    Some((_, Token::Close)) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    Some((position, token)) => Err(unexpected(*position, token)),
+                    None => Err(ParseError::UnexpectedEnd),
+                }
+            }
+            token => Err(unexpected(position, &token)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Expr {
+        Expr::var(name)
+    }
+
+    #[test]
+    fn display_reads_back() {
+        for text in [
+            "a",
+            "¬a",
+            "¬¬a",
+            "a ∧ b ∨ c",
+            "(a ∨ b) ∧ ¬(c → d)",
+            "a ↔ b ⊕ c → ¬d",
+            "((a))",
+            "¬(a ∧ (b ∨ ¬c)) ↔ (d ⊕ e)",
+        ] {
+            let expr = parse_wff(text).unwrap();
+            assert_eq!(parse_wff(&expr.to_string()).unwrap(), expr, "{}", text);
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            parse_wff("a ∨ b ∧ c").unwrap(),
+            Expr::or(var("a"), Expr::and(var("b"), var("c")))
+        );
+        assert_eq!(
+            parse_wff("¬a ∧ b").unwrap(),
+            Expr::and(Expr::not(var("a")), var("b"))
+        );
+    }
+
+    #[test]
+    fn associativity() {
+        assert_eq!(
+            parse_wff("a ∧ b ∧ c").unwrap(),
+            Expr::and(Expr::and(var("a"), var("b")), var("c"))
+        );
+        assert_eq!(
+            parse_wff("a → b → c").unwrap(),
+            Expr::implies(var("a"), Expr::implies(var("b"), var("c")))
+        );
+    }
+
+    #[test]
+    fn double_negation() {
+        assert_eq!(parse_wff("¬¬a").unwrap(), Expr::not(Expr::not(var("a"))));
+    }
+}
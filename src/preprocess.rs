@@ -0,0 +1,311 @@
+use crate::collections::HashMap;
+use crate::collections::HashSet;
+use crate::time;
+use crate::time::Instant;
+use crate::wff::Clause;
+use crate::wff::Formula;
+use crate::wff::Literal;
+use crate::wff::Variable;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// A simplification of a formula. `apply` returns whether it changed anything.
+// Passes keep `formula.variables`, so a variable whose clauses are all
+// removed is still part of the models.
+pub trait Preprocessor<V = String> {
+    fn apply(&self, formula: &mut Formula<V>) -> bool;
+}
+
+// Drops every clause containing a literal whose negation occurs nowhere.
+// Only satisfiability is preserved: the removed clauses hold once such
+// literals are set true, which the remaining clauses do not require.
+pub struct PureLiteral;
+
+// Drops every clause that contains all the literals of another.
+pub struct Subsumption;
+
+// Removes repeated literals and drops clauses containing both a literal and
+// its negation.
+pub struct TautologyRemoval;
+
+// Where (C ∨ l) and (D ∨ ¬l) are clauses and C is part of D, removes ¬l
+// from the second, as resolving the two gives D. Tautologies are left
+// alone, as resolving on one of their variables proves nothing.
+pub struct SelfSubsumption;
+
+impl<V: Variable> Preprocessor<V> for PureLiteral {
+    fn apply(&self, formula: &mut Formula<V>) -> bool {
+        let pure: HashSet<Literal<V>> = {
+            let present: HashSet<&Literal<V>> = formula.literals().collect();
+            present
+                .iter()
+                .filter(|l| !present.contains(&l.negate()))
+                .map(|l| (*l).clone())
+                .collect()
+        };
+        let before = formula.clauses.len();
+        formula
+            .clauses
+            .retain(|clause| !clause.literals.iter().any(|l| pure.contains(l)));
+        formula.clauses.len() != before
+    }
+}
+
+impl<V: Variable> Preprocessor<V> for Subsumption {
+    fn apply(&self, formula: &mut Formula<V>) -> bool {
+        let before = formula.clauses.len();
+        remove_subsumed(formula, None);
+        formula.clauses.len() != before
+    }
+}
+
+impl<V: Variable> Preprocessor<V> for TautologyRemoval {
+    fn apply(&self, formula: &mut Formula<V>) -> bool {
+        let normalized = normalize(&formula.clauses);
+        let changed = normalized.len() != formula.clauses.len()
+            || normalized
+                .iter()
+                .any(|(i, clause)| clause.literals.len() != formula.clauses[*i].literals.len());
+        formula.clauses = normalized.into_iter().map(|(_, clause)| clause).collect();
+        changed
+    }
+}
+
+impl<V: Variable> Preprocessor<V> for SelfSubsumption {
+    fn apply(&self, formula: &mut Formula<V>) -> bool {
+        let mut changed = false;
+        for c in 0..formula.clauses.len() {
+            for d in 0..formula.clauses.len() {
+                if c == d {
+                    continue;
+                }
+                let (short, long) = (&formula.clauses[c], &formula.clauses[d]);
+                if short.is_tautology() || long.is_tautology() {
+                    continue;
+                }
+                let mut pivots = short.literals.iter().filter(|l| !long.literals.contains(l));
+                let pivot = match (pivots.next(), pivots.next()) {
+                    (Some(pivot), None) => pivot.negate(),
+                    _ => continue,
+                };
+                let rest_included = short
+                    .literals
+                    .iter()
+                    .filter(|l| **l != pivot.negate())
+                    .all(|l| *l != pivot && long.literals.contains(l));
+                if rest_included && long.literals.contains(&pivot) {
+                    formula.clauses[d].literals.retain(|l| *l != pivot);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+// Runs its passes in order, over and over, until a whole round changes
+// nothing.
+pub struct PreprocessPipeline<V = String> {
+    passes: Vec<Box<dyn Preprocessor<V>>>,
+}
+
+impl<V> Default for PreprocessPipeline<V> {
+    fn default() -> PreprocessPipeline<V> {
+        PreprocessPipeline { passes: Vec::new() }
+    }
+}
+
+impl<V: Variable> PreprocessPipeline<V> {
+    pub fn new() -> PreprocessPipeline<V> {
+        PreprocessPipeline::default()
+    }
+
+    pub fn with(mut self, pass: impl Preprocessor<V> + 'static) -> PreprocessPipeline<V> {
+        self.passes.push(Box::new(pass));
+        self
+    }
+}
+
+impl<V: Variable> Preprocessor<V> for PreprocessPipeline<V> {
+    fn apply(&self, formula: &mut Formula<V>) -> bool {
+        let mut changed = false;
+        loop {
+            let mut round = false;
+            for pass in &self.passes {
+                round |= pass.apply(formula);
+            }
+            if !round {
+                return changed;
+            }
+            changed = true;
+        }
+    }
+}
+
+// Drops tautologies, repeated literals and subsumed clauses, none of which
+// changes the models of the formula. Once `deadline` passes the remaining
+// subsumption checks are skipped, leaving the formula simplified as far as
+// it got. Returns whether every check ran.
+pub fn simplify<V: Variable>(formula: &mut Formula<V>, deadline: Option<Instant>) -> bool {
+    simplify_tracked(formula, deadline).0
+}
+
+// `simplify`, also returning the position each remaining clause had before.
+pub fn simplify_tracked<V: Variable>(
+    formula: &mut Formula<V>,
+    deadline: Option<Instant>,
+) -> (bool, Vec<usize>) {
+    let normalized = normalize(&formula.clauses);
+    let origins: Vec<usize> = normalized.iter().map(|(i, _)| *i).collect();
+    formula.clauses = normalized.into_iter().map(|(_, clause)| clause).collect();
+    let (complete, kept) = remove_subsumed(formula, deadline);
+    (complete, kept.into_iter().map(|i| origins[i]).collect())
+}
+
+// The clauses without repeated literals or tautologies, each with its
+// position in `clauses`.
+fn normalize<V: Variable>(clauses: &[Clause<V>]) -> Vec<(usize, Clause<V>)> {
+    let mut normalized: Vec<(usize, Clause<V>)> = Vec::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        let mut literals: Vec<Literal<V>> = Vec::new();
+        for literal in &clause.literals {
+            if !literals.contains(literal) {
+                literals.push(literal.clone());
+            }
+        }
+        if !literals.iter().any(|l| literals.contains(&l.negate())) {
+            normalized.push((i, Clause::new(literals)));
+        }
+    }
+    normalized
+}
+
+// Returns whether every check ran, and the positions of the clauses kept.
+fn remove_subsumed<V: Variable>(
+    formula: &mut Formula<V>,
+    deadline: Option<Instant>,
+) -> (bool, Vec<usize>) {
+    let (complete, removed) = subsumed(&formula.clauses, deadline);
+    let kept: Vec<usize> = (0..formula.clauses.len())
+        .filter(|i| !removed.contains(i))
+        .collect();
+    formula.clauses = kept.iter().map(|i| formula.clauses[*i].clone()).collect();
+    (complete, kept)
+}
+
+// Whether every check ran, and the positions of the clauses subsumed by
+// others.
+fn subsumed<V: Variable>(
+    clauses: &[Clause<V>],
+    deadline: Option<Instant>,
+) -> (bool, HashSet<usize>) {
+    let expired = || deadline.is_some_and(time::passed);
+
+    let mut occurrences: HashMap<&Literal<V>, Vec<usize>> = HashMap::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        for literal in &clause.literals {
+            occurrences.entry(literal).or_default().push(i);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..clauses.len()).collect();
+    order.sort_by_key(|i| clauses[*i].literals.len());
+
+    let mut removed: HashSet<usize> = HashSet::new();
+    let mut complete = true;
+    for d in order {
+        if expired() {
+            complete = false;
+            break;
+        }
+        if removed.contains(&d) {
+            continue;
+        }
+        // Every clause D subsumes also contains its rarest literal.
+        let rarest = clauses[d]
+            .literals
+            .iter()
+            .min_by_key(|l| (occurrences[l].len(), l.value.key(), l.negation));
+        let candidates = match rarest {
+            Some(literal) => &occurrences[literal],
+            None => continue,
+        };
+        for &c in candidates {
+            if c != d
+                && !removed.contains(&c)
+                && clauses[d]
+                    .literals
+                    .iter()
+                    .all(|l| clauses[c].literals.contains(l))
+            {
+                removed.insert(c);
+            }
+        }
+    }
+
+    (complete, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::brute_force_sat;
+    use crate::solver::SolverResult;
+    use alloc::string::ToString;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    fn formula(text: &str) -> Formula {
+        crate::parse_text_cnf(text).unwrap()
+    }
+
+    #[test]
+    fn self_subsumption_skips_tautologies() {
+        let mut f = formula("-2 2\n2 1\n-1\n");
+        SelfSubsumption.apply(&mut f);
+        assert_eq!(brute_force_sat(&f), SolverResult::Satisfied);
+    }
+
+    #[test]
+    fn self_subsumption_strengthens() {
+        let mut f = formula("a b\n-a b c\n");
+        assert!(SelfSubsumption.apply(&mut f));
+        assert_eq!(
+            f.clauses[1].to_string(),
+            formula("b c\n").clauses[0].to_string()
+        );
+    }
+
+    #[test]
+    fn pipeline_preserves_satisfiability() {
+        let mut rng = StdRng::seed_from_u64(246);
+        for _ in 0..500 {
+            let n = rng.gen_range(1..8);
+            let clauses: Vec<Clause> = (0..rng.gen_range(0..12))
+                .map(|_| {
+                    let literals = (0..rng.gen_range(1..4))
+                        .map(|_| Literal::new(rng.gen_range(1..=n).to_string(), rng.gen()))
+                        .collect();
+                    Clause::new(literals)
+                })
+                .collect();
+            let original = Formula::new(clauses);
+            let mut simplified = original.clone();
+            PreprocessPipeline::new()
+                .with(SelfSubsumption)
+                .with(Subsumption)
+                .with(PureLiteral)
+                .with(TautologyRemoval)
+                .apply(&mut simplified);
+            assert_eq!(
+                brute_force_sat(&simplified),
+                brute_force_sat(&original),
+                "{} became {}",
+                original,
+                simplified
+            );
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::collections::HashMap;
+use crate::collections::HashSet;
+use crate::wff::Clause;
+use crate::wff::Formula;
+use crate::wff::Variable;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProofFormat {
+    // Each lemma with its clause ID and the IDs of the clauses it follows from.
+    Lrat,
+    // Each lemma alone, left for the checker to re-derive by unit propagation.
+    Rup,
+}
+
+pub struct Proof<V = String> {
+    format: ProofFormat,
+    variable_ids: HashMap<V, u64>,
+    // The id of the next variable to appear after the proof began.
+    next_variable_id: u64,
+    lines: Vec<String>,
+}
+
+impl<V: Variable> Proof<V> {
+    pub fn new(formula: &Formula<V>, format: ProofFormat) -> Proof<V> {
+        let variable_ids = variable_ids(&formula.variables);
+        let next_variable_id = variable_ids.values().max().copied().unwrap_or(0) + 1;
+        Proof {
+            format,
+            variable_ids,
+            next_variable_id,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn format(&self) -> ProofFormat {
+        self.format
+    }
+
+    // `id` and `hints` are 1-based clause IDs, the input clauses taking
+    // 1..=m in the order they appear in the formula. RUP output omits both.
+    // Variables added to the formula after the proof began are numbered
+    // after every other as they first appear.
+    pub fn add(&mut self, id: usize, clause: &Clause<V>, hints: &[usize]) {
+        let mut tokens: Vec<String> = Vec::new();
+        if self.format == ProofFormat::Lrat {
+            tokens.push(id.to_string());
+        }
+        for literal in &clause.literals {
+            let var = *self
+                .variable_ids
+                .entry(literal.value.clone())
+                .or_insert_with(|| {
+                    self.next_variable_id += 1;
+                    self.next_variable_id - 1
+                });
+            if literal.negation {
+                tokens.push(format!("-{}", var));
+            } else {
+                tokens.push(var.to_string());
+            }
+        }
+        tokens.push("0".to_string());
+        if self.format == ProofFormat::Lrat {
+            for hint in hints {
+                tokens.push(hint.to_string());
+            }
+            tokens.push("0".to_string());
+        }
+        self.lines.push(tokens.join(" "));
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+// Variables named by positive integers keep their number, any others are
+// numbered after the largest one in sorted order.
+pub fn variable_ids<V: Variable>(variables: &HashSet<V>) -> HashMap<V, u64> {
+    let mut ids: HashMap<V, u64> = HashMap::new();
+    let mut named: Vec<&V> = Vec::new();
+    for variable in variables {
+        match variable.number() {
+            Some(id) if id > 0 => {
+                ids.insert(variable.clone(), id);
+            }
+            _ => named.push(variable),
+        }
+    }
+
+    named.sort_by_key(|variable| variable.key());
+    let mut next = ids.values().max().copied().unwrap_or(0);
+    for variable in named {
+        next += 1;
+        ids.insert(variable.clone(), next);
+    }
+    ids
+}
+
+// Checks that `lemma` follows from `clauses` by reverse unit propagation and
+// returns the 0-based indices of the clauses involved, in propagation order and
+// ending with the falsified clause.
+pub fn rup_hints<V: Variable>(clauses: &[Clause<V>], lemma: &Clause<V>) -> Option<Vec<usize>> {
+    let mut values: HashMap<&V, (bool, Option<usize>)> = HashMap::new();
+    for literal in &lemma.literals {
+        values.insert(&literal.value, (literal.negation, None));
+    }
+
+    let mut order: Vec<usize> = Vec::new();
+    loop {
+        let mut progress = false;
+        for (i, clause) in clauses.iter().enumerate() {
+            let mut satisfied = false;
+            let mut free = Vec::new();
+            for literal in &clause.literals {
+                match values.get(&literal.value) {
+                    Some((value, _)) => {
+                        if *value != literal.negation {
+                            satisfied = true;
+                            break;
+                        }
+                    }
+                    None => {
+                        if !free.contains(&literal) {
+                            free.push(literal);
+                        }
+                    }
+                }
+            }
+            if satisfied {
+                continue;
+            }
+
+            match free.len() {
+                0 => {
+                    let mut needed: HashSet<usize> = HashSet::new();
+                    let mut stack = vec![i];
+                    while let Some(j) = stack.pop() {
+                        for literal in &clauses[j].literals {
+                            if let Some((_, Some(reason))) = values.get(&literal.value) {
+                                if *reason != j && needed.insert(*reason) {
+                                    stack.push(*reason);
+                                }
+                            }
+                        }
+                    }
+                    let mut hints: Vec<usize> =
+                        order.into_iter().filter(|j| needed.contains(j)).collect();
+                    hints.push(i);
+                    return Some(hints);
+                }
+                1 => {
+                    let literal = free[0];
+                    values.insert(&literal.value, (!literal.negation, Some(i)));
+                    order.push(i);
+                    progress = true;
+                }
+                _ => {}
+            }
+        }
+        if !progress {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BranchingHeuristic;
+    use crate::config::SolverConfig;
+    use crate::solver::CdclSolver;
+    use crate::solver::SolverResult;
+    use crate::wff::Literal;
+
+    #[test]
+    fn lrat_proof_ends_in_the_empty_clause() {
+        let formula =
+            crate::parse_dimacs_cnf("p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n").unwrap();
+        let mut solver = CdclSolver::new(formula);
+        solver.enable_proof(ProofFormat::Lrat);
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Unresolved);
+        let proof = solver.proof().unwrap();
+        let mut known: HashSet<usize> = (1..=4).collect();
+        let mut last = String::new();
+        for line in proof.lines() {
+            let tokens: Vec<usize> = line
+                .split_whitespace()
+                .map(|token| token.trim_start_matches('-').parse().unwrap())
+                .collect();
+            let split = tokens[1..].iter().position(|token| *token == 0).unwrap() + 1;
+            for hint in &tokens[split + 1..tokens.len() - 1] {
+                assert!(known.contains(hint), "unknown clause {} in {}", hint, line);
+            }
+            assert!(known.insert(tokens[0]));
+            last = line.to_string();
+        }
+        assert_eq!(last.split_whitespace().nth(1), Some("0"));
+    }
+
+    #[test]
+    fn variables_added_later_get_ids() {
+        let formula = crate::parse_text_cnf("1 2\n").unwrap();
+        let mut proof = Proof::new(&formula, ProofFormat::Rup);
+        let clause = Clause::new(vec![
+            Literal::new(String::from("x"), true),
+            Literal::new(String::from("2"), false),
+        ]);
+        proof.add(2, &clause, &[]);
+        assert_eq!(proof.lines(), ["-3 2 0"]);
+    }
+
+    #[test]
+    fn rup_lemmas_follow_by_unit_propagation() {
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(220);
+        let mut refuted = 0;
+        for _ in 0..20 {
+            let mut dimacs = String::from("p cnf 15 80\n");
+            for _ in 0..80 {
+                for _ in 0..3 {
+                    let variable: i32 = rng.gen_range(1..=15);
+                    let sign = if rng.gen() { -1 } else { 1 };
+                    dimacs.push_str(&format!("{} ", sign * variable));
+                }
+                dimacs.push_str("0\n");
+            }
+            let formula = crate::parse_dimacs_cnf(&dimacs).unwrap();
+            let mut clauses = formula.clauses.clone();
+            let mut solver = CdclSolver::new(formula);
+            solver.enable_proof(ProofFormat::Rup);
+            solver.solve();
+
+            let proof = solver.proof().unwrap();
+            for line in proof.lines() {
+                let literals: Vec<i32> = line
+                    .split_whitespace()
+                    .map(|token| token.parse().unwrap())
+                    .collect();
+                assert_eq!(literals.last(), Some(&0), "{}", line);
+                let lemma = Clause::new(
+                    literals[..literals.len() - 1]
+                        .iter()
+                        .map(|l| Literal::new(l.unsigned_abs(), *l < 0))
+                        .collect(),
+                );
+                assert!(rup_hints(&clauses, &lemma).is_some(), "{} is not RUP", line);
+                clauses.push(lemma);
+            }
+            let refutes = proof.lines().last() == Some("0");
+            assert_eq!(refutes, *solver.sat() == SolverResult::Unresolved);
+            refuted += refutes as usize;
+        }
+        assert!(refuted > 0 && refuted < 20);
+    }
+
+    #[test]
+    fn proof_size_is_counted() {
+        // Four pigeons, three holes; pigeon i in hole j is variable 3i + j + 1.
+        let mut clauses: Vec<String> = Vec::new();
+        for i in 0..4 {
+            clauses.push(format!("{} {} {} 0", 3 * i + 1, 3 * i + 2, 3 * i + 3));
+        }
+        for j in 1..=3 {
+            for a in 0..4 {
+                for b in a + 1..4 {
+                    clauses.push(format!("-{} -{} 0", 3 * a + j, 3 * b + j));
+                }
+            }
+        }
+        let dimacs = format!("p cnf 12 {}\n{}\n", clauses.len(), clauses.join("\n"));
+
+        for heuristic in [
+            BranchingHeuristic::FirstUnassigned,
+            BranchingHeuristic::Vsids,
+            BranchingHeuristic::Dlis,
+        ] {
+            let config = SolverConfig::default().branching(heuristic).seed(Some(253));
+            let mut plain =
+                CdclSolver::with_config(crate::parse_dimacs_cnf(&dimacs).unwrap(), config.clone());
+            plain.solve();
+            assert_eq!(plain.statistics().proof_lemmas, 0);
+            assert_eq!(plain.statistics().proof_hints, 0);
+
+            let mut solver =
+                CdclSolver::with_config(crate::parse_dimacs_cnf(&dimacs).unwrap(), config);
+            solver.enable_proof(ProofFormat::Lrat);
+            solver.solve();
+            assert_eq!(*solver.sat(), SolverResult::Unresolved);
+            let proof = solver.proof().unwrap();
+            let statistics = solver.statistics();
+            assert!(statistics.proof_lemmas > 1);
+            assert!(statistics.proof_lemmas <= statistics.learned_clauses + 1);
+            assert_eq!(statistics.proof_lemmas, proof.lines().count() as u64);
+            // Each line is `id literals 0 hints 0`.
+            let hints: usize = proof
+                .lines()
+                .map(|line| line.split_whitespace().skip_while(|t| *t != "0").count() - 2)
+                .sum();
+            assert_eq!(statistics.proof_hints, hints as u64);
+        }
+    }
+}
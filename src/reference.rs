@@ -0,0 +1,75 @@
+use crate::collections::HashMap;
+use crate::solver::SolverResult;
+use crate::wff::Formula;
+use crate::wff::Variable;
+use alloc::vec::Vec;
+
+// Decides the formula by trying every assignment of the variables its
+// clauses mention, as an oracle to check the solver against. Takes 2^n
+// steps, so it is only meant for formulas over a couple of dozen variables.
+pub fn brute_force_sat<V: Variable>(formula: &Formula<V>) -> SolverResult {
+    let mut variables: Vec<&V> = formula.literals().map(|l| &l.value).collect();
+    variables.sort_by_key(|v| v.key());
+    variables.dedup();
+    assert!(variables.len() < 64, "too many variables to enumerate");
+    let index: HashMap<&V, usize> = variables.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+
+    for bits in 0..1u64 << variables.len() {
+        let satisfied = formula.clauses.iter().all(|clause| {
+            clause
+                .literals
+                .iter()
+                .any(|l| (bits >> index[&l.value] & 1 == 1) != l.negation)
+        });
+        if satisfied {
+            return SolverResult::Satisfied;
+        }
+    }
+    SolverResult::Unresolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BranchingHeuristic;
+    use crate::config::SolverConfig;
+    use crate::solver::CdclSolver;
+    use crate::wff::Clause;
+    use crate::wff::Literal;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn solver_agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(281);
+        let heuristics = [
+            BranchingHeuristic::Random,
+            BranchingHeuristic::FirstUnassigned,
+            BranchingHeuristic::JeroslowWang,
+            BranchingHeuristic::Dlis,
+            BranchingHeuristic::Vsids,
+        ];
+        for i in 0..500 {
+            let n = rng.gen_range(1..10);
+            let clauses: Vec<Clause<u32>> = (0..rng.gen_range(0..n * 5))
+                .map(|_| {
+                    let literals = (0..rng.gen_range(1..4))
+                        .map(|_| Literal::new(rng.gen_range(1..=n), rng.gen()))
+                        .collect();
+                    Clause::new(literals)
+                })
+                .collect();
+            let formula = Formula::new(clauses);
+            let config = SolverConfig::default()
+                .seed(Some(i))
+                .branching(heuristics[i as usize % heuristics.len()].clone());
+            let mut solver = CdclSolver::with_config(formula.clone(), config);
+            solver.solve();
+            assert_eq!(*solver.sat(), brute_force_sat(&formula), "{}", formula);
+            if *solver.sat() == SolverResult::Satisfied {
+                assert!(solver.verify_model(), "{}", formula);
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::error::Error;
+use crate::parser::ParseError;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Clone)]
+pub struct SearchNode {
+    pub variable: String,
+    pub value: bool,
+    pub level: i32,
+    // Decisions made below this one, in the order they were made.
+    pub children: Vec<usize>,
+    // Set when a conflict backjumped out of this decision.
+    pub pruned: bool,
+}
+
+// Every decision of a search, each a child of the decision that was open
+// when it was made. Nodes are referred to by their index in `nodes`.
+#[derive(Clone, Default)]
+pub struct SearchTree {
+    pub nodes: Vec<SearchNode>,
+    pub roots: Vec<usize>,
+    path: Vec<usize>,
+}
+
+impl SearchTree {
+    pub fn new() -> SearchTree {
+        SearchTree::default()
+    }
+
+    pub fn decide(&mut self, variable: String, value: bool, level: i32) {
+        let index = self.nodes.len();
+        self.nodes.push(SearchNode {
+            variable,
+            value,
+            level,
+            children: Vec::new(),
+            pruned: false,
+        });
+        match self.path.last() {
+            Some(parent) => self.nodes[*parent].children.push(index),
+            None => self.roots.push(index),
+        }
+        self.path.push(index);
+    }
+
+    // Marks the decisions above `level` as pruned and leaves them.
+    pub fn backjump(&mut self, level: i32) {
+        while let Some(index) = self.path.last() {
+            if self.nodes[*index].level <= level {
+                break;
+            }
+            self.nodes[*index].pruned = true;
+            self.path.pop();
+        }
+    }
+
+    // Leaves every open decision without marking it, as when the solver
+    // starts over.
+    pub fn restart(&mut self) {
+        self.path.clear();
+    }
+
+    fn write_node(&self, f: &mut fmt::Formatter, index: usize, depth: usize) -> fmt::Result {
+        let node = &self.nodes[index];
+        write!(
+            f,
+            "{}{} = {} @{}",
+            "  ".repeat(depth),
+            node.variable,
+            node.value,
+            node.level
+        )?;
+        if node.pruned {
+            write!(f, " (pruned)")?;
+        }
+        writeln!(f)?;
+        for child in &node.children {
+            self.write_node(f, *child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+// One decision per line, `<variable> = <true|false> @<level>`, followed by
+// ` (pruned)` if a conflict backjumped out of it. Each decision is indented
+// two spaces more than the one it was made under, and comes after the
+// decisions made before it under the same parent. `FromStr` reads it back.
+impl fmt::Display for SearchTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for root in &self.roots {
+            self.write_node(f, *root, 0)?;
+        }
+        Ok(())
+    }
+}
+
+// Reads the format written by `Display`. The tree read has no open
+// decisions, as after a finished search.
+impl FromStr for SearchTree {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SearchTree, Error> {
+        let mut tree = SearchTree::new();
+        for (i, line) in s.lines().enumerate() {
+            let invalid = || Error::Parse(ParseError::InvalidSearchNode { line: i + 1 });
+            let node = line.trim_start_matches(' ');
+            let indent = line.len() - node.len();
+            if indent % 2 != 0 || indent / 2 > tree.path.len() {
+                return Err(invalid());
+            }
+            let (node, pruned) = match node.strip_suffix(" (pruned)") {
+                Some(node) => (node, true),
+                None => (node, false),
+            };
+            let (node, level) = node.rsplit_once(" @").ok_or_else(invalid)?;
+            let (variable, value) = node.rsplit_once(" = ").ok_or_else(invalid)?;
+            if variable.is_empty() || variable.contains(char::is_whitespace) {
+                return Err(invalid());
+            }
+            let value: bool = value.parse().map_err(|_| invalid())?;
+            let level: i32 = level.parse().map_err(|_| invalid())?;
+            tree.path.truncate(indent / 2);
+            tree.decide(variable.to_string(), value, level);
+            tree.nodes.last_mut().unwrap().pruned = pruned;
+        }
+        tree.restart();
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BranchingHeuristic, SolverConfig};
+    use crate::solver::sink;
+    use crate::solver::CdclSolver;
+
+    #[test]
+    fn records_decisions_and_pruned_branches() {
+        let formula = crate::parse_dimacs_cnf("p cnf 2 2\n1 2 0\n1 -2 0\n").unwrap();
+        let config = SolverConfig::default().branching(BranchingHeuristic::FirstUnassigned);
+        let mut solver = CdclSolver::with_config(formula, config);
+        solver.set_output(sink());
+        solver.enable_search_tree();
+        solver.solve();
+        let tree = solver.search_tree().unwrap();
+        // 1 = false conflicts and the learned unit 1 prunes it. 2 is then
+        // decided with the phase it had when 1 was undone.
+        assert_eq!(tree.to_string(), "1 = false @1 (pruned)\n2 = true @1\n");
+        assert!(tree.nodes.iter().any(|node| node.pruned));
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let text =
+            "a = false @1\n  b = true @2 (pruned)\n  c = false @2\n    d = true @3\nb = false @1\n";
+        let tree: SearchTree = text.parse().unwrap();
+        assert_eq!(tree.roots, [0, 4]);
+        assert_eq!(tree.nodes[0].children, [1, 2]);
+        assert!(tree.nodes[1].pruned);
+        assert_eq!(tree.to_string(), text);
+    }
+
+    #[test]
+    fn rejects_malformed_nodes() {
+        for (text, line) in [
+            ("a = yes @1\n", 1),
+            ("a = true @1\n    b = true @3\n", 2),
+            ("a = true\n", 1),
+        ] {
+            assert_eq!(
+                text.parse::<SearchTree>().err(),
+                Some(Error::Parse(ParseError::InvalidSearchNode { line }))
+            );
+        }
+    }
+}
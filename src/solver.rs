@@ -1,64 +1,299 @@
+use crate::collections::HashMap;
+use crate::collections::HashSet;
+use crate::config::BranchingHeuristic;
+use crate::config::FreePolicy;
+use crate::config::SolverConfig;
+use crate::config::Verbosity;
+use crate::error::Error;
+use crate::model::Model;
+use crate::preprocess;
+use crate::proof;
+use crate::proof::Proof;
+use crate::proof::ProofFormat;
+use crate::search_tree::SearchTree;
+use crate::statistics::Statistics;
+use crate::time;
+use crate::time::Instant;
 use crate::wff::Clause;
 use crate::wff::Formula;
 use crate::wff::Literal;
+use crate::wff::Variable;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Reverse;
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::fmt::Write;
+use core::hash::Hash;
+use core::mem;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::Rng;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use rand::SeedableRng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+// Write a line to the solver's output unless its verbosity is below the
+// macro's: `info!` for search events, `trace!` for every assignment.
+macro_rules! info {
+    ($solver:expr, $($arg:tt)*) => {
+        if $solver.config.verbosity >= Verbosity::Info {
+            let _ = writeln!($solver.output, $($arg)*);
+        }
+    };
+}
+
+macro_rules! trace {
+    ($solver:expr, $($arg:tt)*) => {
+        if $solver.config.verbosity >= Verbosity::Trace {
+            let _ = writeln!($solver.output, $($arg)*);
+        }
+    };
+}
+
+// Where the search log goes: a byte stream with `std`, and any `fmt::Write`
+// without it.
+pub type Output = Box<dyn Write + Send + Sync>;
+
+#[cfg(not(feature = "std"))]
+struct Sink;
+
+#[cfg(not(feature = "std"))]
+impl Write for Sink {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+// Discards everything written to it.
+#[cfg(feature = "std")]
+pub(crate) fn sink() -> Output {
+    Box::new(io::sink())
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn sink() -> Output {
+    Box::new(Sink)
+}
+
+// Teaching checks enumerate every assignment, so they stop here.
+pub const TEACHING_MAX_VARIABLES: usize = 12;
+
+// Identifies a clause of the solver from the moment it is added. Clauses are
+// numbered 1, 2, ... in the order they were added, and keep their number when
+// other clauses are removed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct ClauseId(pub u32);
 
 #[derive(Clone)]
 pub struct Assignment {
     pub value: bool,
-    pub antecedent: Option<Clause>,
+    pub antecedent: Option<ClauseId>,
     pub dl: i32,
+    // Grows along the trail: a later assignment has a larger one.
+    pub order: u64,
 }
 
 impl Assignment {
-    pub fn new(value: bool, antecedent: Option<Clause>, dl: i32) -> Assignment {
+    pub fn new(value: bool, antecedent: Option<ClauseId>, dl: i32) -> Assignment {
         Assignment {
             value,
             antecedent,
             dl,
+            order: 0,
         }
     }
 }
 
-pub struct Assignments {
-    pub assignments: HashMap<String, Assignment>,
+// Keyed on the variables of the literals, so named ones by default.
+pub struct Assignments<V = String> {
+    pub assignments: HashMap<V, Assignment>,
     dl: i32,
+    next_order: u64,
+    // The assigned variables in the order they were assigned.
+    trail: Vec<V>,
+    // How many assignments each decision level holds.
+    level_sizes: Vec<usize>,
 }
 
-impl Assignments {
-    pub fn new() -> Assignments {
+impl<V> Default for Assignments<V> {
+    fn default() -> Assignments<V> {
         Assignments {
             assignments: HashMap::new(),
             dl: 0,
+            next_order: 0,
+            trail: Vec::new(),
+            level_sizes: Vec::new(),
         }
     }
+}
+
+impl<V: Eq + Hash + Clone> Assignments<V> {
+    pub fn new() -> Assignments<V> {
+        Assignments::default()
+    }
 
-    pub fn assign(&mut self, variable: String, value: bool, antecedent: Option<Clause>) {
-        let assignment = Assignment::new(value, antecedent, self.dl);
+    pub fn assign(&mut self, variable: V, value: bool, antecedent: Option<ClauseId>) {
+        let mut assignment = Assignment::new(value, antecedent, self.dl);
+        assignment.order = self.next_order;
+        self.next_order += 1;
+        let level = self.dl.max(0) as usize;
+        if self.level_sizes.len() <= level {
+            self.level_sizes.resize(level + 1, 0);
+        }
+        self.level_sizes[level] += 1;
+        self.remove(&variable);
+        self.trail.push(variable.clone());
         self.assignments.insert(variable, assignment);
     }
 
-    pub fn remove(&mut self, variable: &String) {
-        self.assignments.remove(variable);
+    pub fn remove(&mut self, variable: &V) {
+        if let Some(assignment) = self.assignments.remove(variable) {
+            self.level_sizes[assignment.dl.max(0) as usize] -= 1;
+            // Usually the latest assignment, so the trail is searched from its end.
+            if let Some(i) = self.trail.iter().rposition(|v| v == variable) {
+                self.trail.remove(i);
+            }
+        }
     }
 
-    pub fn get(&self, variable: &String) -> Option<&Assignment> {
+    pub fn get(&self, variable: &V) -> Option<&Assignment> {
         self.assignments.get(variable)
     }
+
+    pub fn trail(&self) -> &[V] {
+        &self.trail
+    }
+
+    // Removes every assignment above `level`, returning them latest first.
+    // After chronological backtracking an assignment can be below a later one
+    // on the trail; those are kept in place. Only the end of the trail back
+    // to the earliest removed assignment is looked at.
+    pub fn backtrack(&mut self, level: i32) -> Vec<(V, Assignment)> {
+        let start = (level.max(-1) + 1) as usize;
+        let mut remaining: usize = self.level_sizes.iter().skip(start).sum();
+        let mut removed = Vec::with_capacity(remaining);
+        let mut kept = Vec::new();
+        while remaining > 0 {
+            let variable = self.trail.pop().unwrap();
+            if self.assignments[&variable].dl > level {
+                let assignment = self.assignments.remove(&variable).unwrap();
+                removed.push((variable, assignment));
+                remaining -= 1;
+            } else {
+                kept.push(variable);
+            }
+        }
+        self.trail.extend(kept.into_iter().rev());
+        self.level_sizes.truncate(start);
+        removed
+    }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SolverResult {
     Satisfied,
     Unresolved,
 }
 
-pub struct CdclSolver {
-    pub formula: Formula,
-    assignments: Assignments,
+// How far a search got before running out of time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IncompleteResult {
+    pub max_decision_level: i32,
+    pub assigned_variables: usize,
+}
+
+// Why a formula is UNSAT without any decisions: the unit propagations, in
+// order, that falsify `conflict`.
+#[derive(Clone)]
+pub struct UnsatExplanation<V = String> {
+    pub propagations: Vec<(Literal<V>, Clause<V>)>,
+    pub conflict: Clause<V>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LiteralRelation {
+    // Equal in every model.
+    Equivalent,
+    // Different in every model.
+    Opposite,
+    Independent,
+}
+
+pub struct CdclSolver<V = String> {
+    pub formula: Formula<V>,
+    config: SolverConfig,
+    assignments: Assignments<V>,
     sat: SolverResult,
+    saved_phases: HashMap<V, bool>,
+    // Values of the longest trail reached without a conflict.
+    best_trail: HashMap<V, bool>,
+    // VSIDS activities, and the amount the next bump adds. Decaying every
+    // activity is done by growing the bump instead, which keeps the order.
+    activities: HashMap<V, f64>,
+    activity_bump: f64,
+    // Branching priorities; variables not listed have priority 0.
+    priorities: HashMap<V, u32>,
+    // The id of each clause of `formula`, in the same order.
+    clause_ids: Vec<ClauseId>,
+    clause_positions: HashMap<ClauseId, usize>,
+    // Each clause by its canonical literals, to notice a clause learned again.
+    clause_index: HashMap<Vec<Literal<V>>, ClauseId>,
+    // The positions of the clauses each literal occurs in.
+    occurrences: HashMap<Literal<V>, Vec<usize>>,
+    next_clause_id: u32,
+    // Learned clauses still in the formula, with their literal block
+    // distance: the number of decision levels among their literals when
+    // learned.
+    learned: HashMap<ClauseId, u32>,
+    // Learned unit clauses, asserted at level 0 after every reset.
+    learned_units: Vec<ClauseId>,
+    // The assumptions of the solve in progress, decided in order before any
+    // other variable.
+    assumptions: Vec<Literal<V>>,
+    // The assumptions behind the last UNSAT result under assumptions, and
+    // the ones among them the result depends on.
+    failed_assumptions: Vec<Literal<V>>,
+    unsat_core: Vec<Literal<V>>,
+    proof: Option<Proof<V>>,
+    search_tree: Option<SearchTree>,
+    statistics: Statistics,
+    watches: HashMap<Literal<V>, Vec<usize>>,
+    watched_literals: Vec<Option<(Literal<V>, Literal<V>)>>,
+    watch_processed: HashSet<V>,
+    // How many leading clauses of `formula.clauses` the assignments up to
+    // each decision level satisfy, as far as the search has looked.
+    satisfied_prefix: Vec<usize>,
+    rng: StdRng,
+    // Searches stop at the next decision once this has passed.
+    deadline: Option<Instant>,
+    // Likewise once the conflict count goes past this.
+    conflict_limit: Option<u64>,
+    max_decision_level: i32,
+    // Conflicts since the last restart, and how many restarts came before.
+    restart_conflicts: u64,
+    restart_index: u64,
+    unsat_explanation: Option<UnsatExplanation<V>>,
+    preprocessed: bool,
+    // Clauses dropped as satisfied by a pure literal, put back on reset.
+    pure_clauses: Vec<Clause<V>>,
+    // After a solve by components, whose models all end up at level 0, the
+    // literals fixed within them.
+    component_fixed: Option<Vec<Literal<V>>>,
+    // Receives the search trace; stdout unless replaced with `set_output`,
+    // and discarded without `std`.
+    output: Output,
 }
 
 pub enum ClauseStatus {
@@ -73,16 +308,153 @@ pub enum UnitPropagationResult {
     Unresolved,
 }
 
-impl CdclSolver {
-    pub fn new(formula: Formula) -> CdclSolver {
-        CdclSolver {
+impl<V: Variable> CdclSolver<V> {
+    pub fn new(formula: Formula<V>) -> CdclSolver<V> {
+        CdclSolver::with_config(formula, SolverConfig::default())
+    }
+
+    // `new` drawing every random choice from `seed`, so that runs repeat the
+    // same decisions.
+    pub fn with_seed(formula: Formula<V>, seed: u64) -> CdclSolver<V> {
+        CdclSolver::with_config(formula, SolverConfig::default().seed(Some(seed)))
+    }
+
+    pub fn with_config(formula: Formula<V>, config: SolverConfig) -> CdclSolver<V> {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            #[cfg(feature = "std")]
+            None => StdRng::from_entropy(),
+            // Without `std` there is no entropy to draw on.
+            #[cfg(not(feature = "std"))]
+            None => StdRng::seed_from_u64(0),
+        };
+        let mut solver = CdclSolver {
             formula,
+            config,
             assignments: Assignments::new(),
             sat: SolverResult::Unresolved,
+            saved_phases: HashMap::new(),
+            best_trail: HashMap::new(),
+            activities: HashMap::new(),
+            activity_bump: 1.0,
+            priorities: HashMap::new(),
+            clause_ids: Vec::new(),
+            clause_positions: HashMap::new(),
+            clause_index: HashMap::new(),
+            occurrences: HashMap::new(),
+            next_clause_id: 1,
+            learned: HashMap::new(),
+            learned_units: Vec::new(),
+            assumptions: Vec::new(),
+            failed_assumptions: Vec::new(),
+            unsat_core: Vec::new(),
+            proof: None,
+            search_tree: None,
+            statistics: Statistics::default(),
+            watches: HashMap::new(),
+            watched_literals: Vec::new(),
+            watch_processed: HashSet::new(),
+            satisfied_prefix: Vec::new(),
+            rng,
+            deadline: None,
+            conflict_limit: None,
+            max_decision_level: 0,
+            restart_conflicts: 0,
+            restart_index: 0,
+            unsat_explanation: None,
+            preprocessed: false,
+            pure_clauses: Vec::new(),
+            component_fixed: None,
+            #[cfg(feature = "std")]
+            output: Box::new(io::stdout()),
+            #[cfg(not(feature = "std"))]
+            output: sink(),
+        };
+        for literal in solver.formula.literals() {
+            *solver
+                .activities
+                .entry(literal.value.clone())
+                .or_insert(0.0) += 1.0;
+        }
+        solver.register_clauses();
+        solver
+    }
+
+    pub fn clause(&self, id: ClauseId) -> Option<&Clause<V>> {
+        self.clause_positions
+            .get(&id)
+            .map(|i| &self.formula.clauses[*i])
+    }
+
+    pub fn clause_ids(&self) -> &[ClauseId] {
+        &self.clause_ids
+    }
+
+    // The positions in `formula.clauses` of the clauses containing `literal`,
+    // in increasing order.
+    pub fn clauses_with(&self, literal: &Literal<V>) -> &[usize] {
+        self.occurrences.get(literal).map_or(&[], Vec::as_slice)
+    }
+
+    // Numbers the clauses pushed onto `formula.clauses` since the last call.
+    // Rebuilds the positions, the index and the occurrences after clauses
+    // were removed.
+    fn index_clauses(&mut self) {
+        self.satisfied_prefix.clear();
+        self.clause_positions = self
+            .clause_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        self.clause_index.clear();
+        self.occurrences.clear();
+        for (i, (clause, id)) in self
+            .formula
+            .clauses
+            .iter()
+            .zip(&self.clause_ids)
+            .enumerate()
+        {
+            self.clause_index.entry(clause.canonical()).or_insert(*id);
+            for literal in &clause.literals {
+                let positions = self.occurrences.entry(literal.clone()).or_default();
+                if positions.last() != Some(&i) {
+                    positions.push(i);
+                }
+            }
+        }
+    }
+
+    fn register_clauses(&mut self) {
+        for i in self.clause_ids.len()..self.formula.clauses.len() {
+            let id = ClauseId(self.next_clause_id);
+            self.next_clause_id += 1;
+            self.clause_ids.push(id);
+            self.clause_positions.insert(id, i);
+            self.clause_index
+                .entry(self.formula.clauses[i].canonical())
+                .or_insert(id);
+            for literal in &self.formula.clauses[i].literals {
+                let positions = self.occurrences.entry(literal.clone()).or_default();
+                if positions.last() != Some(&i) {
+                    positions.push(i);
+                }
+            }
         }
     }
 
-    pub fn assignments(&self) -> &Assignments {
+    // For ids held by assignments and propagation results, which always refer
+    // to clauses still present.
+    fn clause_ref(&self, id: ClauseId) -> &Clause<V> {
+        &self.formula.clauses[self.clause_positions[&id]]
+    }
+
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    pub fn assignments(&self) -> &Assignments<V> {
         &self.assignments
     }
 
@@ -90,208 +462,1851 @@ impl CdclSolver {
         &self.sat
     }
 
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    // Set by a solve that found the formula UNSAT by unit propagation alone.
+    pub fn unsat_explanation(&self) -> Option<&UnsatExplanation<V>> {
+        self.unsat_explanation.as_ref()
+    }
+
+    // The values of the formula's variables, with the ones the search left
+    // free filled in by the free variable policy. None unless the formula is
+    // SAT.
+    pub fn model(&self) -> Option<Model<V>> {
+        if !matches!(self.sat, SolverResult::Satisfied) {
+            return None;
+        }
+        let mut model = Model {
+            values: HashMap::new(),
+            free: HashSet::new(),
+        };
+        for variable in &self.formula.variables {
+            match self.model_value(variable) {
+                Some(value) => {
+                    model.values.insert(variable.clone(), value);
+                }
+                None if self.config.free_variable_policy == FreePolicy::Mark => {
+                    model.free.insert(variable.clone());
+                }
+                None => {}
+            }
+        }
+        Some(model)
+    }
+
+    // The value of `variable` in the model: its assignment, or for a free
+    // variable whatever the free variable policy gives it, if anything.
+    fn model_value(&self, variable: &V) -> Option<bool> {
+        match (
+            self.assignments.assignments.get(variable),
+            self.config.free_variable_policy,
+        ) {
+            (Some(assignment), _) => Some(assignment.value),
+            (None, FreePolicy::AllTrue) => Some(true),
+            (None, FreePolicy::AllFalse) => Some(false),
+            (None, FreePolicy::Omit | FreePolicy::Mark) => None,
+        }
+    }
+
+    // A witness for a SAT result that can be checked by hand: the model, then
+    // each original clause with a literal of it that the model makes true.
+    pub fn sat_certificate(&self) -> String {
+        let model = match self.model() {
+            Some(model) => model,
+            None => return String::from("No certificate, the formula is not SAT.\n"),
+        };
+        let mut certificate = String::from("Model:\n");
+        let mut variables: Vec<&V> = model.values.keys().chain(&model.free).collect();
+        variables.sort_by_key(|variable| variable.key());
+        for variable in variables {
+            match model.values.get(variable) {
+                Some(value) => certificate.push_str(&format!("{} = {}\n", variable, value)),
+                None => certificate.push_str(&format!("{} is free\n", variable)),
+            }
+        }
+        certificate.push_str("Clauses:\n");
+        for (clause, id) in self.formula.clauses.iter().zip(&self.clause_ids) {
+            if self.learned.contains_key(id) {
+                continue;
+            }
+            let witness = clause
+                .literals
+                .iter()
+                .find(|l| model.values.get(&l.value) == Some(&!l.negation));
+            let line = match witness {
+                Some(literal) => format!("{} is satisfied by {}\n", clause, literal),
+                None => format!("{} is NOT satisfied\n", clause),
+            };
+            certificate.push_str(&line);
+        }
+        certificate
+    }
+
+    // Looks up one variable of the model without building all of it, so it
+    // agrees with `model()`, free variables included. None unless the formula
+    // is SAT, mentions `var` and the model gives it a value.
+    pub fn value_of<Q>(&self, var: &Q) -> Option<bool>
+    where
+        V: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let variable = self.formula.variables.get(var)?;
+        if !matches!(self.sat, SolverResult::Satisfied) {
+            return None;
+        }
+        self.model_value(variable)
+    }
+
+    // Literals established at decision level 0: input and learned units,
+    // what they imply and pure literals. Assumptions are decided above it, as
+    // they only hold for the current solve.
+    pub fn fixed_literals(&self) -> Vec<Literal<V>> {
+        if let Some(fixed) = &self.component_fixed {
+            return fixed.clone();
+        }
+        let mut fixed: Vec<Literal<V>> = self
+            .assignments
+            .assignments
+            .iter()
+            .filter(|(_, assignment)| assignment.dl == 0)
+            .map(|(variable, assignment)| Literal::new(variable.clone(), !assignment.value))
+            .collect();
+        fixed.sort_by(|a, b| a.value.key().cmp(&b.value.key()));
+        fixed
+    }
+
+    pub fn set_output(&mut self, output: Output) {
+        self.output = output;
+    }
+
+    pub fn enable_proof(&mut self, format: ProofFormat) {
+        self.proof = Some(Proof::new(&self.formula, format));
+    }
+
+    pub fn proof(&self) -> Option<String> {
+        self.proof.as_ref().map(|proof| proof.lines().join("\n"))
+    }
+
+    pub fn enable_search_tree(&mut self) {
+        self.search_tree = Some(SearchTree::new());
+    }
+
+    pub fn search_tree(&self) -> Option<&SearchTree> {
+        self.search_tree.as_ref()
+    }
+
+    pub fn saved_phases(&self) -> &HashMap<V, bool> {
+        &self.saved_phases
+    }
+
+    pub fn best_trail(&self) -> &HashMap<V, bool> {
+        &self.best_trail
+    }
+
+    pub fn rephase(&mut self) {
+        info!(
+            self,
+            "Rephasing to the best trail of {} assignments",
+            self.best_trail.len()
+        );
+        for (variable, value) in &self.best_trail {
+            self.saved_phases.insert(variable.clone(), *value);
+        }
+    }
+
+    pub fn set_initial_assignment(&mut self, model: &HashMap<V, bool>) {
+        for (variable, value) in model {
+            self.saved_phases.insert(variable.clone(), *value);
+        }
+    }
+
+    // Decisions always go to an unassigned variable of the highest priority
+    // present, the branching heuristic choosing among those.
+    pub fn set_branching_priority<Q>(&mut self, var: &Q, priority: u32) -> Result<(), Error>
+    where
+        V: Borrow<Q>,
+        Q: Hash + Eq + fmt::Display + ?Sized,
+    {
+        let variable = self.check_variable(var)?.clone();
+        self.priorities.insert(variable, priority);
+        Ok(())
+    }
+
+    // The formula's own copy of `var`.
+    fn check_variable<Q>(&self, var: &Q) -> Result<&V, Error>
+    where
+        V: Borrow<Q>,
+        Q: Hash + Eq + fmt::Display + ?Sized,
+    {
+        self.formula
+            .variables
+            .get(var)
+            .ok_or_else(|| Error::UnknownVariable(var.to_string()))
+    }
+
     pub fn solve(&mut self) {
-        let (reason, _) = self.unit_propagation();
-        if matches!(reason, UnitPropagationResult::Conflict) {
+        self.failed_assumptions.clear();
+        self.unsat_core.clear();
+        if self.config.decompose && self.proof.is_none() {
+            self.solve_components();
+        } else {
+            if self.config.pure_literals && self.proof.is_none() && self.assignments.dl == 0 {
+                self.eliminate_pure_literals();
+            }
+            self.search(true);
+        }
+    }
+
+    // Re-checks a core found by an earlier solve, such as a MUS from
+    // `mus::all_muses`, before solving in full. When every clause of `core` is
+    // still in the formula and they alone are UNSAT, so is the formula, and
+    // that is all that gets solved. The conflicts of the check count towards
+    // `statistics()` either way.
+    pub fn solve_with_core_hint(&mut self, core: &[Clause<V>]) {
+        let present = core.iter().all(|hint| {
+            let hint: HashSet<&Literal<V>> = hint.literals.iter().collect();
+            self.formula
+                .clauses
+                .iter()
+                .any(|clause| clause.literals.iter().collect::<HashSet<_>>() == hint)
+        });
+        if present && !core.is_empty() {
+            let mut checker = CdclSolver::with_config(
+                Formula::new(core.to_vec()),
+                self.config.clone().decompose(false),
+            );
+            checker.set_output(sink());
+            let satisfiable = checker.is_satisfiable();
+            self.statistics.add(&checker.statistics);
+            if !satisfiable {
+                info!(self, "Core hint is still UNSAT");
+                self.reset();
+                return;
+            }
+        }
+        info!(self, "Core hint no longer applies");
+        self.solve();
+    }
+
+    // Solves every connected component with a solver of its own, stopping at
+    // the first UNSAT one. The models of the components are combined as
+    // level 0 assignments, and their statistics are added up.
+    fn solve_components(&mut self) {
+        self.reset();
+        let components = self.formula.connected_components();
+        info!(self, "Solving {} components separately", components.len());
+        let config = self.config.clone().decompose(false);
+        let mut fixed: Vec<Literal<V>> = Vec::new();
+        for component in components {
+            let mut solver = CdclSolver::with_config(component, config.clone());
+            solver.set_output(sink());
+            solver.set_initial_assignment(&self.saved_phases);
+            for (variable, priority) in &self.priorities {
+                let _ = solver.set_branching_priority(variable, *priority);
+            }
+            solver.solve();
+            self.statistics.add(&solver.statistics);
+            if !matches!(solver.sat, SolverResult::Satisfied) {
+                self.sat = SolverResult::Unresolved;
+                return;
+            }
+            for variable in solver.assignments.trail() {
+                let value = solver.assignments.assignments[variable].value;
+                self.assignments.assign(variable.clone(), value, None);
+            }
+            fixed.extend(solver.fixed_literals());
+        }
+        fixed.sort_by(|a, b| a.value.key().cmp(&b.value.key()));
+        self.component_fixed = Some(fixed);
+        self.sat = SolverResult::Satisfied;
+    }
+
+    #[cfg(feature = "std")]
+    // Gives up once `timeout` has elapsed, reporting the progress made, but
+    // not before making a decision. The assignments are kept, so calling
+    // again resumes the same search.
+    pub fn solve_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<SolverResult, IncompleteResult> {
+        self.deadline = Some(Instant::now() + timeout);
+        self.max_decision_level = self.assignments.dl;
+        let finished = self.search(true);
+        self.deadline = None;
+        if finished {
+            Ok(self.sat)
+        } else {
+            Err(IncompleteResult {
+                max_decision_level: self.max_decision_level,
+                assigned_variables: self.assignments.assignments.len(),
+            })
+        }
+    }
+
+    // Gives up with None once more than `max_conflicts` conflicts happened in
+    // this call. The limit is checked before each decision, so the conflicts
+    // that follow the last one can go past it. Learned clauses and
+    // assignments are kept, as with a timeout.
+    pub fn solve_with_limit(&mut self, max_conflicts: u64) -> Option<SolverResult> {
+        self.conflict_limit = Some(self.statistics.conflicts + max_conflicts);
+        let finished = self.search(true);
+        self.conflict_limit = None;
+        finished.then_some(self.sat)
+    }
+
+    // Stops as soon as every clause is satisfied, so variables the search never
+    // needed to decide are left unassigned in `assignments()`.
+    pub fn is_satisfiable(&mut self) -> bool {
+        self.search(false);
+        matches!(self.sat, SolverResult::Satisfied)
+    }
+
+    // Assumptions are decided before anything else, each one not yet implied
+    // on a decision level of its own above 0, so a conflict they cause is
+    // learned from like any other and level 0 only ever holds what follows
+    // from the formula. The search gives up once an assumption is false by
+    // the ones before it, possibly through learned clauses. They only hold
+    // for this call: after UNSAT the solver is back at level 0, and after SAT
+    // the model keeps them until the next solve. Learned clauses and activities carry over, as
+    // every learned clause follows from the formula alone. Assumed variables
+    // the formula does not mention are added to it. After UNSAT,
+    // `failed_assumptions` holds the assumptions that were set when it was
+    // found.
+    pub fn solve_under_assumptions(&mut self, assumptions: &[Literal<V>]) -> SolverResult {
+        self.reset();
+        self.failed_assumptions.clear();
+        self.unsat_core.clear();
+        for literal in assumptions {
+            self.formula.variables.insert(literal.value.clone());
+        }
+        self.assumptions = assumptions.to_vec();
+        self.search(true);
+        self.assumptions.clear();
+        if matches!(self.sat, SolverResult::Unresolved) {
+            if self.failed_assumptions.is_empty() {
+                self.failed_assumptions = assumptions.to_vec();
+            }
+            self.backjump(0);
+        }
+        self.sat
+    }
+
+    pub fn failed_assumptions(&self) -> &[Literal<V>] {
+        &self.failed_assumptions
+    }
+
+    // After UNSAT under assumptions, the assumptions it depends on, so the
+    // formula with these alone is UNSAT. Empty when the formula is UNSAT by
+    // itself, or after SAT.
+    pub fn unsat_core(&self) -> Vec<Literal<V>> {
+        self.unsat_core.clone()
+    }
+
+    // The assumptions that make `failed` false, found by following
+    // antecedents back from it to the decisions above level 0, which are all
+    // assumptions while one is still to be decided, and `failed` itself.
+    fn assumption_core(&self, failed: &Literal<V>) -> Vec<Literal<V>> {
+        let mut core: Vec<Literal<V>> = vec![failed.clone()];
+        let mut pending: Vec<V> = vec![failed.value.clone()];
+        let mut visited: HashSet<V> = HashSet::new();
+        while let Some(variable) = pending.pop() {
+            if !visited.insert(variable.clone()) {
+                continue;
+            }
+            let assignment = match self.assignments.get(&variable) {
+                Some(assignment) if assignment.dl > 0 => assignment,
+                _ => continue,
+            };
+            match assignment.antecedent.map(|id| self.clause_ref(id)) {
+                Some(antecedent) => pending.extend(
+                    antecedent
+                        .literals
+                        .iter()
+                        .filter(|l| l.value != variable)
+                        .map(|l| l.value.clone()),
+                ),
+                None => core.push(Literal::new(variable, !assignment.value)),
+            }
+        }
+        core.retain(|literal| self.assumptions.contains(literal));
+        core.sort_by(|a, b| a.value.key().cmp(&b.value.key()));
+        core.dedup();
+        core
+    }
+
+    // Decides the relation with up to four solves under assumptions, leaving
+    // the solver in the state of the last one.
+    pub fn relation(&mut self, a: &Literal<V>, b: &Literal<V>) -> Result<LiteralRelation, Error> {
+        self.check_variable(&a.value)?;
+        self.check_variable(&b.value)?;
+        if !self.satisfiable_under(&[a.clone(), b.negate()])
+            && !self.satisfiable_under(&[a.negate(), b.clone()])
+        {
+            return Ok(LiteralRelation::Equivalent);
+        }
+        if !self.satisfiable_under(&[a.clone(), b.clone()])
+            && !self.satisfiable_under(&[a.negate(), b.negate()])
+        {
+            return Ok(LiteralRelation::Opposite);
+        }
+        Ok(LiteralRelation::Independent)
+    }
+
+    fn satisfiable_under(&mut self, assumptions: &[Literal<V>]) -> bool {
+        matches!(
+            self.solve_under_assumptions(assumptions),
+            SolverResult::Satisfied
+        )
+    }
+
+    // Keeps a satisfying model that already satisfies `clause`, only deciding
+    // the variables new to it, and solves again from scratch (keeping
+    // learned clauses) when it does not.
+    pub fn add_clause(&mut self, clause: Clause<V>) {
+        for literal in &clause.literals {
+            self.formula.variables.insert(literal.value.clone());
+        }
+        let satisfied = matches!(self.sat, SolverResult::Satisfied)
+            && matches!(self.clause_status(&clause), ClauseStatus::Satisfied);
+        self.formula.clauses.push(clause);
+        self.register_clauses();
+
+        if satisfied {
+            self.search(true);
+        } else {
+            self.reset();
+            self.solve();
+        }
+    }
+
+    fn reset(&mut self) {
+        if !self.pure_clauses.is_empty() {
+            self.formula.clauses.append(&mut self.pure_clauses);
+            self.register_clauses();
+        }
+        self.assignments = Assignments::new();
+        self.sat = SolverResult::Unresolved;
+        self.unsat_explanation = None;
+        self.component_fixed = None;
+        self.watch_processed.clear();
+        if let Some(tree) = self.search_tree.as_mut() {
+            tree.restart();
+        }
+        for id in &self.learned_units {
+            if let Some(unit) = self.clause(*id) {
+                let literal = &unit.literals[0];
+                self.assignments
+                    .assign(literal.value.clone(), !literal.negation, Some(*id));
+            }
+        }
+    }
+
+    // Simplifies the clauses within `preprocess_timeout`, returning whether it
+    // finished. Watches are dropped, to be set up again on the new clauses.
+    // The search is undone above level 0, and so are the assignments and
+    // learned clauses that refer to clauses which were removed; propagation
+    // implies them again from the clauses that subsumed them.
+    pub fn preprocess(&mut self) -> bool {
+        self.preprocessed = true;
+        #[cfg(feature = "std")]
+        let deadline = self
+            .config
+            .preprocess_timeout
+            .map(|timeout| Instant::now() + timeout);
+        #[cfg(not(feature = "std"))]
+        let deadline = None;
+        let before = self.formula.clauses.len();
+        self.register_clauses();
+        let (complete, kept) = preprocess::simplify_tracked(&mut self.formula, deadline);
+        self.clause_ids = kept.iter().map(|i| self.clause_ids[*i]).collect();
+        self.index_clauses();
+        self.backjump(0);
+        let positions = &self.clause_positions;
+        let orphaned: Vec<V> = self
+            .assignments
+            .assignments
+            .iter()
+            .filter(|(_, assignment)| {
+                assignment
+                    .antecedent
+                    .is_some_and(|id| !positions.contains_key(&id))
+            })
+            .map(|(variable, _)| variable.clone())
+            .collect();
+        for variable in &orphaned {
+            self.assignments.remove(variable);
+        }
+        self.learned.retain(|id, _| positions.contains_key(id));
+        self.learned_units.retain(|id| positions.contains_key(id));
+        info!(
+            self,
+            "Preprocessing removed {} clauses{}",
+            before - self.formula.clauses.len(),
+            if complete { "" } else { " before timing out" }
+        );
+        self.watches.clear();
+        self.watched_literals.clear();
+        self.watch_processed.clear();
+        complete
+    }
+
+    // Sets every pure literal true at level 0 and drops the original clauses
+    // it satisfies, which can leave more literals pure, until there are none.
+    pub fn eliminate_pure_literals(&mut self) {
+        self.register_clauses();
+        let mut eliminated = 0;
+        loop {
+            let mut pure: Vec<Literal<V>> = self
+                .occurrences
+                .iter()
+                .filter(|(_, positions)| !positions.is_empty())
+                .map(|(literal, _)| literal)
+                .filter(|l| self.clauses_with(&l.negate()).is_empty())
+                .filter(|l| self.assignments.get(&l.value).is_none())
+                .cloned()
+                .collect();
+            if pure.is_empty() {
+                break;
+            }
+            pure.sort_by(|a, b| a.value.key().cmp(&b.value.key()));
+            let removed: HashSet<ClauseId> = pure
+                .iter()
+                .flat_map(|literal| self.clauses_with(literal))
+                .map(|i| self.clause_ids[*i])
+                .filter(|id| !self.learned.contains_key(id))
+                .collect();
+            for literal in &pure {
+                trace!(
+                    self,
+                    "Pure literal, assigning {} = {}",
+                    literal.value,
+                    !literal.negation
+                );
+                self.assignments
+                    .assign(literal.value.clone(), !literal.negation, None);
+            }
+            eliminated += pure.len();
+            let clauses = mem::take(&mut self.formula.clauses);
+            let (dropped, kept): (Vec<_>, Vec<_>) = clauses
+                .into_iter()
+                .zip(&self.clause_ids)
+                .partition(|(_, id)| removed.contains(*id));
+            self.formula.clauses = kept.into_iter().map(|(clause, _)| clause).collect();
+            self.pure_clauses
+                .extend(dropped.into_iter().map(|(clause, _)| clause));
+            self.clause_ids.retain(|id| !removed.contains(id));
+            self.index_clauses();
+        }
+        if eliminated == 0 {
             return;
         }
+        // Watches refer to positions, so every clause is watched afresh.
+        self.watches.clear();
+        self.watched_literals.clear();
+        self.watch_processed.clear();
+        info!(self, "Eliminated {} pure literals", eliminated);
+        self.statistics.pure_literals += eliminated as u64;
+    }
+
+    // Returns false when the deadline or conflict limit cut the search short.
+    fn search(&mut self, complete_model: bool) -> bool {
+        // Nothing satisfies an empty clause, so there is nothing to search.
+        self.register_clauses();
+        if let Some(i) = self
+            .formula
+            .clauses
+            .iter()
+            .position(|c| c.literals.is_empty())
+        {
+            info!(self, "The formula contains the empty clause");
+            self.sat = SolverResult::Unresolved;
+            self.unsat_explanation = Some(self.explain_conflict(self.clause_ids[i]));
+            self.log_proof_step(Clause::new(Vec::new()));
+            return true;
+        }
+        // Any assignment satisfies a formula without clauses. Once pure
+        // literals took them all, the variables they left behind still get
+        // values.
+        if self.formula.clauses.is_empty()
+            && self.pure_clauses.is_empty()
+            && self.assumptions.is_empty()
+        {
+            self.sat = SolverResult::Satisfied;
+            return true;
+        }
+        if self.config.preprocess && !self.preprocessed && self.proof.is_none() {
+            self.preprocess();
+        }
+        let (reason, clause) = self.propagate();
+        if matches!(reason, UnitPropagationResult::Conflict) {
+            if self.assignments.dl == 0 {
+                self.unsat_explanation = Some(self.explain_conflict(clause.unwrap()));
+            }
+            self.log_proof_step(Clause::new(Vec::new()));
+            return true;
+        }
 
-        while !self.all_variables_assigned() {
-            let (var, val) = self.pick_branching_variable();
-            println!("Guessing {} = {}", var, val);
-            println!("Decision level: {}", self.assignments.dl);
+        // Each call makes at least one decision before the deadline is
+        // checked, so that resuming with short timeouts still gets somewhere.
+        let decisions = self.statistics.decisions;
+        while self.next_assumption().is_some()
+            || !self.all_variables_assigned() && (complete_model || !self.satisfied_so_far())
+        {
+            if self.deadline.is_some_and(time::passed) && self.statistics.decisions > decisions
+                || self
+                    .conflict_limit
+                    .is_some_and(|limit| self.statistics.conflicts > limit)
+            {
+                return false;
+            }
+            let (var, val) = if let Some(i) = self.next_assumption() {
+                let assumption = self.assumptions[i].clone();
+                if self.literal_value(&assumption) == Some(false) {
+                    info!(self, "Assumption {} is false", assumption);
+                    self.failed_assumptions = self.assumptions[..=i].to_vec();
+                    self.unsat_core = self.assumption_core(&assumption);
+                    self.sat = SolverResult::Unresolved;
+                    return true;
+                }
+                trace!(self, "Assuming {}", assumption);
+                (assumption.value, !assumption.negation)
+            } else {
+                let (var, val) = match self.pick_branching_variable() {
+                    Some(decision) => decision,
+                    None => break,
+                };
+                trace!(self, "Guessing {} = {}", var, val);
+                (var, val)
+            };
+            trace!(self, "Decision level: {}", self.assignments.dl);
             self.assignments.dl += 1;
+            self.max_decision_level = self.max_decision_level.max(self.assignments.dl);
+            self.statistics.max_decision_level =
+                self.statistics.max_decision_level.max(self.assignments.dl);
+            if let Some(tree) = self.search_tree.as_mut() {
+                tree.decide(var.to_string(), val, self.assignments.dl);
+            }
             self.assignments.assign(var, val, None);
+            self.statistics.decisions += 1;
 
             loop {
-                let (reason, clause) = self.unit_propagation();
+                let (reason, clause) = self.propagate();
                 if !matches!(reason, UnitPropagationResult::Conflict) {
+                    if self.config.rephase_interval.is_some()
+                        && self.assignments.assignments.len() > self.best_trail.len()
+                    {
+                        self.best_trail = self.current_values();
+                    }
                     break;
                 }
+                self.statistics.conflicts += 1;
+                if let Some(interval) = self.config.rephase_interval {
+                    if self.statistics.conflicts.is_multiple_of(interval) {
+                        self.rephase();
+                    }
+                }
 
-                let (b, learnt_clause) = self.conflict_analysis(clause.as_ref().unwrap());
+                let (mut b, learnt_clause) =
+                    self.conflict_analysis(self.clause_ref(clause.unwrap()));
+                if let Some(learnt) = learnt_clause.as_ref() {
+                    self.bump_activities(learnt);
+                }
                 if b < 0 {
-                    return;
+                    self.log_proof_step(Clause::new(Vec::new()));
+                    return true;
                 }
-
-                if let Some(learnt) = learnt_clause {
-                    self.add_learned_clause(learnt);
+                if let (Some(threshold), Some(learnt)) =
+                    (self.config.chrono_threshold, learnt_clause.as_ref())
+                {
+                    if learnt.literals.len() > 1 && (self.assignments.dl - b) as usize > threshold {
+                        b = self.assignments.dl - 1;
+                    }
                 }
+
                 self.backtrack(b);
                 self.assignments.dl = b;
-                println!("Backtracked to decision level {}", b);
+                if cfg!(debug_assertions) && self.config.check_invariants {
+                    self.check_invariants();
+                }
+                if let Some(tree) = self.search_tree.as_mut() {
+                    tree.backjump(b);
+                }
+                trace!(self, "Backtracked to decision level {}", b);
+                let mut learned = None;
+                if let Some(learnt) = learnt_clause {
+                    let unit = learnt.literals.len() == 1;
+                    let id = self.add_learned_clause(learnt);
+                    if unit {
+                        self.assert_learned_unit(id);
+                    } else {
+                        self.assert_learned_clause(id);
+                    }
+                    learned = Some(id);
+                }
+                if let Some(interval) = self.config.reduce_interval {
+                    if self.statistics.conflicts.is_multiple_of(interval) {
+                        self.reduce_learned(learned);
+                    }
+                }
+
+                self.restart_conflicts += 1;
+                if let Some(base) = self.config.restart_base {
+                    if self.restart_conflicts >= base * luby(self.restart_index + 1) {
+                        self.restart();
+                    }
+                }
             }
         }
         self.sat = SolverResult::Satisfied;
+        true
+    }
+
+    // The first assumption that is not true yet, which the next decision is
+    // about.
+    fn next_assumption(&self) -> Option<usize> {
+        self.assumptions
+            .iter()
+            .position(|literal| self.literal_value(literal) != Some(true))
     }
 
-    pub fn clause_status(&self, clause: &Clause) -> ClauseStatus {
-        let mut false_count: i32 = 0;
+    // Drops every decision, keeping the learned clauses and whatever holds at
+    // level 0, so the search starts over with what it has learned.
+    fn restart(&mut self) {
+        info!(self, "Restarting");
+        self.backtrack(0);
+        self.assignments.dl = 0;
+        if let Some(tree) = self.search_tree.as_mut() {
+            tree.restart();
+        }
+        self.restart_conflicts = 0;
+        self.restart_index += 1;
+        self.statistics.restarts += 1;
+    }
+
+    pub fn clause_status(&self, clause: &Clause<V>) -> ClauseStatus {
         let mut true_count: i32 = 0;
+        let mut unassigned_count: i32 = 0;
+        let mut last_unassigned: Option<&Literal<V>> = None;
         for literal in &clause.literals {
             match self.assignments.get(&literal.value) {
                 Some(assignment) => {
-                    if assignment.value == literal.negation {
-                        false_count += 1;
-                    } else {
+                    if assignment.value != literal.negation {
                         true_count += 1;
                     }
                 }
-                None => {}
+                None => {
+                    // Repeats of a literal only count once, any overcount
+                    // needs two distinct unassigned literals anyway.
+                    if last_unassigned != Some(literal) {
+                        unassigned_count += 1;
+                        last_unassigned = Some(literal);
+                    }
+                }
             }
         }
         if true_count > 0 {
-            return ClauseStatus::Satisfied;
-        } else if false_count == clause.literals.len() as i32 {
-            return ClauseStatus::Unsatisfied;
-        } else if false_count == clause.literals.len() as i32 - 1 {
-            return ClauseStatus::Unit;
+            ClauseStatus::Satisfied
+        } else if unassigned_count == 0 {
+            ClauseStatus::Unsatisfied
+        } else if unassigned_count == 1 {
+            ClauseStatus::Unit
         } else {
-            return ClauseStatus::Unresolved;
+            ClauseStatus::Unresolved
+        }
+    }
+
+    // Unit propagation to a fixpoint and nothing else: no output, no
+    // decisions, no learning and no backtracking. Returns the falsified
+    // clause on a conflict, leaving the trail as it was when it was found.
+    // Meant for searches driven from outside together with `decide` and
+    // `backjump`.
+    pub fn propagate_only(&mut self) -> (UnitPropagationResult, Option<Clause<V>>) {
+        let output = mem::replace(&mut self.output, sink());
+        #[cfg(feature = "parallel")]
+        let (result, clause) = self.parallel_unit_propagation();
+        #[cfg(not(feature = "parallel"))]
+        let (result, clause) = self.unit_propagation();
+        self.output = output;
+        (result, clause.map(|id| self.clause_ref(id).clone()))
+    }
+
+    // Opens a new decision level with `literal` set true.
+    pub fn decide(&mut self, literal: &Literal<V>) {
+        self.assignments.dl += 1;
+        self.max_decision_level = self.max_decision_level.max(self.assignments.dl);
+        self.statistics.max_decision_level =
+            self.statistics.max_decision_level.max(self.assignments.dl);
+        self.assignments
+            .assign(literal.value.clone(), !literal.negation, None);
+        self.statistics.decisions += 1;
+    }
+
+    // The literals unit propagation implies once the assumptions are set, not
+    // counting the assumptions, ordered by variable. Fails with a clause the
+    // assumptions falsify: one of the formula, or the negation of an
+    // assumption that was already false. Leaves the solver as it found it.
+    pub fn propagate_assumptions(
+        &mut self,
+        assumptions: &[Literal<V>],
+    ) -> Result<Vec<Literal<V>>, Clause<V>> {
+        let level = self.assignments.dl;
+        let statistics = self.statistics.clone();
+        self.assignments.dl += 1;
+        let mut result = Ok(Vec::new());
+        for literal in assumptions {
+            match self.literal_value(literal) {
+                Some(true) => {}
+                Some(false) => {
+                    result = Err(Clause::new(vec![literal.negate()]));
+                    break;
+                }
+                None => self
+                    .assignments
+                    .assign(literal.value.clone(), !literal.negation, None),
+            }
+        }
+        if result.is_ok() {
+            result = match self.propagate_only() {
+                (UnitPropagationResult::Conflict, clause) => Err(clause.unwrap()),
+                (UnitPropagationResult::Unresolved, _) => {
+                    let mut implied: Vec<Literal<V>> = self
+                        .assignments
+                        .assignments
+                        .iter()
+                        .filter(|(_, assignment)| assignment.dl > level)
+                        .map(|(variable, assignment)| {
+                            Literal::new(variable.clone(), !assignment.value)
+                        })
+                        .filter(|literal| !assumptions.contains(literal))
+                        .collect();
+                    implied.sort_by(|a, b| a.value.key().cmp(&b.value.key()));
+                    Ok(implied)
+                }
+            };
         }
+        self.backjump(level);
+        self.statistics = statistics;
+        result
     }
 
-    pub fn unit_propagation(&mut self) -> (UnitPropagationResult, Option<Clause>) {
+    // Undoes every assignment above `level` and continues from it, silently.
+    pub fn backjump(&mut self, level: i32) {
+        let output = mem::replace(&mut self.output, sink());
+        self.backtrack(level);
+        self.assignments.dl = level;
+        self.output = output;
+    }
+
+    pub fn unit_propagation(&mut self) -> (UnitPropagationResult, Option<ClauseId>) {
+        self.register_clauses();
+        let order = self.scan_order();
         let mut finished: bool = false;
         while !finished {
             finished = true;
-            for clause in &self.formula.clauses {
+            for &i in &order {
+                let clause = &self.formula.clauses[i];
+                if self.is_watched(clause) {
+                    continue;
+                }
+                self.statistics.scanned_clauses += 1;
                 match self.clause_status(clause) {
                     ClauseStatus::Satisfied | ClauseStatus::Unresolved => {}
                     ClauseStatus::Unsatisfied => {
-                        return (UnitPropagationResult::Conflict, Some(clause.clone()));
+                        return (UnitPropagationResult::Conflict, Some(self.clause_ids[i]));
                     }
                     ClauseStatus::Unit => {
                         finished = false;
-                        let mut unit_literal: Option<Literal> = None;
-                        for literal in &clause.literals {
-                            match self.assignments.get(&literal.value) {
-                                Some(_) => {}
-                                None => {
-                                    unit_literal = Some(literal.clone());
-                                    break;
-                                }
-                            }
-                        }
-                        match unit_literal {
-                            Some(literal) => {
-                                println!(
-                                    "Unit propagation, assigning {} = {}",
-                                    literal.value, !literal.negation
-                                );
-
-                                self.assignments.assign(
-                                    literal.value.clone(),
-                                    !literal.negation,
-                                    Some(clause.clone()),
-                                );
-                                println!("Decision level: {}", self.assignments.dl);
+                        if let Some(literal) = self.unit_literal(clause) {
+                            trace!(
+                                self,
+                                "Unit propagation, assigning {} = {}",
+                                literal.value,
+                                !literal.negation
+                            );
+
+                            self.assignments.assign(
+                                literal.value.clone(),
+                                !literal.negation,
+                                Some(self.clause_ids[i]),
+                            );
+                            self.statistics.propagations += 1;
+                            if self.learned.contains_key(&self.clause_ids[i]) {
+                                self.statistics.learned_propagations += 1;
                             }
-                            None => {}
+                            trace!(self, "Decision level: {}", self.assignments.dl);
                         }
                     }
                 }
             }
+
+            if self.config.watch_threshold.is_some() {
+                match self.propagate_watches() {
+                    Err(id) => return (UnitPropagationResult::Conflict, Some(id)),
+                    Ok(true) => finished = false,
+                    Ok(false) => {}
+                }
+            }
         }
-        return (UnitPropagationResult::Unresolved, None);
+        (UnitPropagationResult::Unresolved, None)
     }
 
-    pub fn add_learned_clause(&mut self, clause: Clause) {
-        self.formula.clauses.push(clause);
+    // Positions of the clauses in the order the scan visits them: as stored,
+    // or with `learned_first` the learned clauses newest first and then the
+    // rest.
+    pub fn scan_order(&self) -> Vec<usize> {
+        let positions = 0..self.formula.clauses.len();
+        if !self.config.learned_first {
+            return positions.collect();
+        }
+        let (learned, original): (Vec<usize>, Vec<usize>) =
+            positions.partition(|i| self.learned.contains_key(&self.clause_ids[*i]));
+        learned.into_iter().rev().chain(original).collect()
     }
 
-    pub fn all_variables_assigned(&self) -> bool {
-        return self.assignments.assignments.len() == self.formula.variables.len();
+    // A clause repeating a single literal is left to the scan, as two watches
+    // cannot be placed on it.
+    fn is_watched(&self, clause: &Clause<V>) -> bool {
+        match self.config.watch_threshold {
+            Some(threshold) => {
+                clause.literals.len() > threshold
+                    && clause.literals.iter().any(|l| *l != clause.literals[0])
+            }
+            None => false,
+        }
     }
 
-    pub fn pick_branching_variable(&self) -> (String, bool) {
-        let assigned_vars: HashSet<String> = self.assignments.assignments.keys().cloned().collect();
+    pub fn literal_value(&self, literal: &Literal<V>) -> Option<bool> {
+        self.assignments
+            .get(&literal.value)
+            .map(|assignment| assignment.value != literal.negation)
+    }
 
-        let unassigned_variables: Vec<&String> =
-            self.formula.variables.difference(&assigned_vars).collect();
+    // Returns whether anything was assigned, or the clause that became false.
+    fn propagate_watches(&mut self) -> Result<bool, ClauseId> {
+        let mut assigned = false;
 
-        let mut rng = rand::thread_rng();
+        // Clauses added since the last round are watched and checked once in full.
+        for i in self.watched_literals.len()..self.formula.clauses.len() {
+            if !self.is_watched(&self.formula.clauses[i]) {
+                self.watched_literals.push(None);
+                continue;
+            }
+            let (first, second) = self.pick_watches(&self.formula.clauses[i]);
+            self.watches.entry(first.clone()).or_default().push(i);
+            if second != first {
+                self.watches.entry(second.clone()).or_default().push(i);
+            }
+            self.watched_literals.push(Some((first, second)));
 
-        let random_bool: bool = rng.gen();
-        let random_variable: &String = unassigned_variables.choose(&mut rng).unwrap();
+            let clause = &self.formula.clauses[i];
+            match self.clause_status(clause) {
+                ClauseStatus::Satisfied | ClauseStatus::Unresolved => {}
+                ClauseStatus::Unsatisfied => return Err(self.clause_ids[i]),
+                ClauseStatus::Unit => {
+                    if let Some(literal) = self.unit_literal(clause) {
+                        self.assign_watched(literal, i);
+                        assigned = true;
+                    }
+                }
+            }
+        }
 
-        (random_variable.clone(), random_bool)
-    }
+        let mut queue: Vec<(V, bool)> = self
+            .assignments
+            .assignments
+            .iter()
+            .filter(|(variable, _)| !self.watch_processed.contains(*variable))
+            .map(|(variable, assignment)| (variable.clone(), assignment.value))
+            .collect();
+        queue.sort_by(|a, b| b.0.key().cmp(&a.0.key()));
+        while let Some((variable, value)) = queue.pop() {
+            self.watch_processed.insert(variable.clone());
 
-    pub fn backtrack(&mut self, b: i32) {
-        let mut to_remove: Vec<String> = Vec::new();
-        for (variable, assignment) in &self.assignments.assignments {
-            if assignment.dl > b {
-                to_remove.push(variable.clone());
+            let falsified = Literal::new(variable, value);
+            let watchers = self.watches.get(&falsified).cloned().unwrap_or_default();
+            for i in watchers {
+                let (first, second) = self.watched_literals[i].clone().unwrap();
+                let other = if first == falsified { second } else { first };
+                if self.literal_value(&other) == Some(true) {
+                    continue;
+                }
+
+                let replacement = self.formula.clauses[i]
+                    .literals
+                    .iter()
+                    .find(|l| {
+                        **l != falsified && **l != other && self.literal_value(l) != Some(false)
+                    })
+                    .cloned();
+                if let Some(replacement) = replacement {
+                    self.watches
+                        .get_mut(&falsified)
+                        .unwrap()
+                        .retain(|j| *j != i);
+                    self.watches.entry(replacement.clone()).or_default().push(i);
+                    self.watched_literals[i] = Some((other, replacement));
+                    continue;
+                }
+
+                match self.literal_value(&other) {
+                    Some(_) => {
+                        // Leave the variable to be looked at again once it is
+                        // reassigned after the conflict.
+                        self.watch_processed.remove(&falsified.value);
+                        return Err(self.clause_ids[i]);
+                    }
+                    None => {
+                        queue.push((other.value.clone(), !other.negation));
+                        self.assign_watched(other, i);
+                        assigned = true;
+                    }
+                }
             }
         }
-        for variable in to_remove {
-            println!("Backtracking, removing assignment for {}", variable);
-            self.assignments.remove(&variable);
+        Ok(assigned)
+    }
+
+    // Prefers true literals assigned earliest, then unassigned ones, then false
+    // literals assigned latest, which are the first to be freed by a backjump.
+    fn pick_watches(&self, clause: &Clause<V>) -> (Literal<V>, Literal<V>) {
+        let mut ranked: Vec<((u8, i32), &Literal<V>)> = Vec::new();
+        for literal in &clause.literals {
+            if ranked.iter().any(|(_, l)| *l == literal) {
+                continue;
+            }
+            let dl = self.assignments.get(&literal.value).map_or(0, |a| a.dl);
+            let rank = match self.literal_value(literal) {
+                Some(true) => (0, dl),
+                None => (1, 0),
+                Some(false) => (2, -dl),
+            };
+            ranked.push((rank, literal));
         }
+        ranked.sort_by_key(|(rank, _)| *rank);
+        let first = ranked[0].1.clone();
+        let second = ranked.get(1).map_or(first.clone(), |(_, l)| (*l).clone());
+        (first, second)
     }
 
-    pub fn resolve(&self, a: &Clause, b: &Clause, x: &str) -> Clause {
-        let mut result: HashSet<Literal> = a.literals.iter().cloned().collect();
-        result.extend(b.literals.iter().cloned());
-        result.remove(&Literal::new(x.to_string(), true));
-        result.remove(&Literal::new(x.to_string(), false));
-        Clause::new(result.into_iter().collect())
+    fn assign_watched(&mut self, literal: Literal<V>, i: usize) {
+        trace!(
+            self,
+            "Unit propagation, assigning {} = {}",
+            literal.value,
+            !literal.negation
+        );
+        self.assignments
+            .assign(literal.value, !literal.negation, Some(self.clause_ids[i]));
+        self.statistics.propagations += 1;
+        self.statistics.watched_propagations += 1;
+        trace!(self, "Decision level: {}", self.assignments.dl);
     }
 
-    pub fn conflict_analysis(&self, clause: &Clause) -> (i32, Option<Clause>) {
-        if self.assignments.dl == 0 {
-            return (-1, None);
+    fn bump_activities(&mut self, learnt: &Clause<V>) {
+        for literal in &learnt.literals {
+            *self.activities.entry(literal.value.clone()).or_insert(0.0) += self.activity_bump;
+        }
+        self.activity_bump /= self.config.vsids_decay;
+        if self.activity_bump > 1e100 {
+            for activity in self.activities.values_mut() {
+                *activity *= 1e-100;
+            }
+            self.activity_bump *= 1e-100;
         }
+    }
 
-        let mut current_clause = clause.clone();
-        let mut literals: Vec<Literal> = current_clause
+    pub fn unit_literal(&self, clause: &Clause<V>) -> Option<Literal<V>> {
+        clause
             .literals
             .iter()
-            .filter(|lit| self.assignments.get(&lit.value).unwrap().dl == self.assignments.dl)
+            .find(|literal| self.assignments.get(&literal.value).is_none())
             .cloned()
-            .collect();
+    }
 
-        while literals.len() != 1 {
-            literals = literals
-                .into_iter()
-                .filter(|lit| {
-                    self.assignments
-                        .get(&lit.value)
-                        .unwrap()
-                        .antecedent
-                        .is_some()
+    pub fn unit_clauses(&self) -> Vec<(&Clause<V>, Literal<V>)> {
+        let mut units: Vec<(&Clause<V>, Literal<V>)> = Vec::new();
+        for clause in &self.formula.clauses {
+            if let ClauseStatus::Unit = self.clause_status(clause) {
+                if let Some(literal) = self.unit_literal(clause) {
+                    units.push((clause, literal));
+                }
+            }
+        }
+        units
+    }
+
+    fn propagate(&mut self) -> (UnitPropagationResult, Option<ClauseId>) {
+        let before: Option<HashMap<V, bool>> =
+            if cfg!(debug_assertions) && self.config.verify_propagation {
+                Some(self.current_values())
+            } else {
+                None
+            };
+
+        #[cfg(feature = "parallel")]
+        let result = self.parallel_unit_propagation();
+        #[cfg(not(feature = "parallel"))]
+        let result = self.unit_propagation();
+
+        if let Some(before) = before {
+            if matches!(result.0, UnitPropagationResult::Unresolved) {
+                self.verify_propagation(before);
+            }
+        }
+        if cfg!(debug_assertions)
+            && self.config.check_invariants
+            && matches!(result.0, UnitPropagationResult::Unresolved)
+        {
+            self.check_invariants();
+        }
+        result
+    }
+
+    fn current_values(&self) -> HashMap<V, bool> {
+        self.assignments
+            .assignments
+            .iter()
+            .map(|(variable, assignment)| (variable.clone(), assignment.value))
+            .collect()
+    }
+
+    // Recomputes the round from the assignments it started with using a plain
+    // clause scan, which must reach exactly the same fixpoint.
+    fn verify_propagation(&self, before: HashMap<V, bool>) {
+        let mut expected = before;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in &self.formula.clauses {
+                if clause
+                    .literals
+                    .iter()
+                    .any(|l| expected.get(&l.value) == Some(&!l.negation))
+                {
+                    continue;
+                }
+                let mut free: Vec<&Literal<V>> = Vec::new();
+                for literal in &clause.literals {
+                    if !expected.contains_key(&literal.value) && !free.contains(&literal) {
+                        free.push(literal);
+                    }
+                }
+                assert!(
+                    !free.is_empty(),
+                    "propagation missed a conflict on {}",
+                    clause
+                );
+                if free.len() == 1 {
+                    expected.insert(free[0].value.clone(), !free[0].negation);
+                    changed = true;
+                }
+            }
+        }
+        assert!(
+            expected == self.current_values(),
+            "propagation diverged from the reference scan"
+        );
+    }
+
+    // Checks the bookkeeping around the clauses: ids and positions agree,
+    // every antecedent, learned clause and learned unit is still present,
+    // watches match the watched literals of each clause, and every variable
+    // in a clause belongs to the formula. Clauses pushed onto the formula
+    // directly are not numbered until the next propagation, and are allowed.
+    pub fn verify_clause_db(&self) -> Result<(), Error> {
+        self.clause_db_problem().map_err(Error::Internal)
+    }
+
+    fn clause_db_problem(&self) -> Result<(), String> {
+        let clauses = &self.formula.clauses;
+        if self.clause_ids.len() > clauses.len() {
+            return Err(format!(
+                "{} clause ids for {} clauses",
+                self.clause_ids.len(),
+                clauses.len()
+            ));
+        }
+        if self.clause_positions.len() != self.clause_ids.len() {
+            return Err(format!(
+                "{} clause positions for {} clause ids",
+                self.clause_positions.len(),
+                self.clause_ids.len()
+            ));
+        }
+        for (i, id) in self.clause_ids.iter().enumerate() {
+            if self.clause_positions.get(id) != Some(&i) {
+                return Err(format!("clause {} is not found at position {}", id.0, i));
+            }
+        }
+
+        for (variable, assignment) in &self.assignments.assignments {
+            if let Some(id) = assignment.antecedent {
+                if !self.clause_positions.contains_key(&id) {
+                    return Err(format!(
+                        "the antecedent {} of {} is not a clause",
+                        id.0, variable
+                    ));
+                }
+            }
+        }
+        for id in self.learned.keys().chain(&self.learned_units) {
+            if !self.clause_positions.contains_key(id) {
+                return Err(format!("learned clause {} is not a clause", id.0));
+            }
+        }
+
+        if self.watched_literals.len() > clauses.len() {
+            return Err(format!(
+                "{} watched clauses out of {}",
+                self.watched_literals.len(),
+                clauses.len()
+            ));
+        }
+        for (i, watched) in self.watched_literals.iter().enumerate() {
+            if let Some((first, second)) = watched {
+                for literal in [first, second] {
+                    if !clauses[i].literals.contains(literal) {
+                        return Err(format!(
+                            "clause {} watches {}, which it does not contain",
+                            i, literal
+                        ));
+                    }
+                    if !self.watches.get(literal).is_some_and(|w| w.contains(&i)) {
+                        return Err(format!(
+                            "clause {} is missing from the watches of {}",
+                            i, literal
+                        ));
+                    }
+                }
+            }
+        }
+        for (literal, watchers) in &self.watches {
+            for i in watchers {
+                let watched = self.watched_literals.get(*i).cloned().flatten();
+                if !watched.is_some_and(|(first, second)| first == *literal || second == *literal) {
+                    return Err(format!(
+                        "clause {} is in the watches of {} without watching it",
+                        i, literal
+                    ));
+                }
+            }
+        }
+
+        for literal in self.formula.literals() {
+            if !self.formula.variables.contains(&literal.value) {
+                return Err(format!(
+                    "{} is not a variable of the formula",
+                    literal.value
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Each level up to the current one holds exactly one decision and nothing
+    // is assigned above it. Every implied literal is true in its antecedent,
+    // whose other literals are false and were assigned at the same level or
+    // below.
+    pub fn check_invariants(&self) {
+        let dl = self.assignments.dl;
+        let mut decisions: Vec<usize> = vec![0; dl.max(0) as usize + 1];
+        for (variable, assignment) in &self.assignments.assignments {
+            assert!(
+                (0..=dl).contains(&assignment.dl),
+                "{} is assigned at level {} outside 0..={}",
+                variable,
+                assignment.dl,
+                dl
+            );
+            let antecedent = match assignment.antecedent {
+                Some(id) => self.clause_ref(id),
+                None => {
+                    decisions[assignment.dl as usize] += 1;
+                    continue;
+                }
+            };
+            let implied = Literal::new(variable.clone(), !assignment.value);
+            assert!(
+                antecedent.literals.contains(&implied),
+                "the antecedent of {} does not contain it",
+                variable
+            );
+            for literal in antecedent.literals.iter().filter(|l| **l != implied) {
+                let other = self.assignments.get(&literal.value);
+                assert!(
+                    other.is_some_and(
+                        |other| other.value == literal.negation && other.dl <= assignment.dl
+                    ),
+                    "{} is not falsified below {} in its antecedent",
+                    literal,
+                    variable
+                );
+            }
+        }
+        for (level, count) in decisions.iter().enumerate().skip(1) {
+            assert!(*count == 1, "level {} holds {} decisions", level, count);
+        }
+    }
+
+    // `unit_propagation` with each pass over the scanned clauses split in
+    // two: their statuses are computed in parallel, and the units and
+    // conflicts found are then taken in scan order, serially so that
+    // assignments never race. Clauses a pass assigns into are seen on the next
+    // pass rather than the same one, which reaches the same assignments.
+    #[cfg(feature = "parallel")]
+    pub fn parallel_unit_propagation(&mut self) -> (UnitPropagationResult, Option<ClauseId>) {
+        self.register_clauses();
+        let scanned: Vec<usize> = self
+            .scan_order()
+            .into_iter()
+            .filter(|i| !self.is_watched(&self.formula.clauses[*i]))
+            .collect();
+        let mut finished: bool = false;
+        while !finished {
+            finished = true;
+            self.statistics.scanned_clauses += scanned.len() as u64;
+            let found: Vec<usize> = scanned
+                .par_iter()
+                .copied()
+                .filter(|i| {
+                    matches!(
+                        self.clause_status(&self.formula.clauses[*i]),
+                        ClauseStatus::Unit | ClauseStatus::Unsatisfied
+                    )
                 })
                 .collect();
 
-            if let Some(literal) = literals.first() {
-                let antecedent = self
-                    .assignments
-                    .get(&literal.value)
-                    .unwrap()
-                    .antecedent
-                    .as_ref()
-                    .unwrap();
-                current_clause = self.resolve(&current_clause, antecedent, &literal.value);
+            for i in found {
+                let clause = &self.formula.clauses[i];
+                match self.clause_status(clause) {
+                    ClauseStatus::Satisfied | ClauseStatus::Unresolved => {}
+                    ClauseStatus::Unsatisfied => {
+                        return (UnitPropagationResult::Conflict, Some(self.clause_ids[i]));
+                    }
+                    ClauseStatus::Unit => {
+                        finished = false;
+                        if let Some(literal) = self.unit_literal(clause) {
+                            trace!(
+                                self,
+                                "Unit propagation, assigning {} = {}",
+                                literal.value,
+                                !literal.negation
+                            );
 
-                literals = current_clause
+                            self.assignments.assign(
+                                literal.value.clone(),
+                                !literal.negation,
+                                Some(self.clause_ids[i]),
+                            );
+                            self.statistics.propagations += 1;
+                            if self.learned.contains_key(&self.clause_ids[i]) {
+                                self.statistics.learned_propagations += 1;
+                            }
+                            trace!(self, "Decision level: {}", self.assignments.dl);
+                        }
+                    }
+                }
+            }
+
+            if self.config.watch_threshold.is_some() {
+                match self.propagate_watches() {
+                    Err(id) => return (UnitPropagationResult::Conflict, Some(id)),
+                    Ok(true) => finished = false,
+                    Ok(false) => {}
+                }
+            }
+        }
+        (UnitPropagationResult::Unresolved, None)
+    }
+
+    // Returns the id of the clause, which is the one already present when an
+    // equal clause was there.
+    pub fn add_learned_clause(&mut self, clause: Clause<V>) -> ClauseId {
+        self.register_clauses();
+        if let Some(id) = self.clause_index.get(&clause.canonical()) {
+            self.statistics.duplicate_learned_clauses += 1;
+            return *id;
+        }
+        if self.config.teaching {
+            self.check_learned_clause(&clause);
+        }
+        self.log_proof_step(clause.clone());
+        // Unassigned literals, such as the one the clause asserts after a
+        // backjump, count as a single level.
+        let levels: HashSet<Option<i32>> = clause
+            .literals
+            .iter()
+            .map(|literal| self.assignments.get(&literal.value).map(|a| a.dl))
+            .collect();
+        self.formula.clauses.push(clause);
+        self.register_clauses();
+        let id = self.clause_ids[self.clause_ids.len() - 1];
+        self.learned.insert(id, levels.len() as u32);
+        self.statistics.learned_clauses += 1;
+        id
+    }
+
+    // Deletes half of the learned clauses, highest block distance first and
+    // the oldest first among equals, sparing the reasons for current
+    // assignments, the learned units and `keep`, the clause just learned,
+    // which is about to assert its literal.
+    fn reduce_learned(&mut self, keep: Option<ClauseId>) {
+        let mut protected: HashSet<ClauseId> = self
+            .assignments
+            .assignments
+            .values()
+            .filter_map(|assignment| assignment.antecedent)
+            .collect();
+        protected.extend(&self.learned_units);
+        protected.extend(keep);
+        let mut candidates: Vec<(ClauseId, u32)> = self
+            .learned
+            .iter()
+            .filter(|(id, _)| !protected.contains(*id))
+            .map(|(id, lbd)| (*id, *lbd))
+            .collect();
+        candidates.sort_by_key(|(id, lbd)| (Reverse(*lbd), *id));
+        let removed: HashSet<ClauseId> = candidates
+            .iter()
+            .take(self.learned.len() / 2)
+            .map(|(id, _)| *id)
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+
+        let clauses = mem::take(&mut self.formula.clauses);
+        self.formula.clauses = clauses
+            .into_iter()
+            .zip(&self.clause_ids)
+            .filter(|(_, id)| !removed.contains(*id))
+            .map(|(clause, _)| clause)
+            .collect();
+        self.clause_ids.retain(|id| !removed.contains(id));
+        self.index_clauses();
+        self.learned.retain(|id, _| !removed.contains(id));
+        // Watches refer to positions, so every clause is watched afresh.
+        self.watches.clear();
+        self.watched_literals.clear();
+        self.watch_processed.clear();
+        info!(self, "Deleted {} learned clauses", removed.len());
+        self.statistics.deleted_clauses += removed.len() as u64;
+    }
+
+    // Whether every assignment satisfying the clauses so far satisfies
+    // `clause`, as found by trying them all. None when the formula has more
+    // than `TEACHING_MAX_VARIABLES` variables.
+    pub fn check_learned_clause(&mut self, clause: &Clause<V>) -> Option<bool> {
+        let mut variables: Vec<&V> = self.formula.variables.iter().collect();
+        for literal in &clause.literals {
+            if !self.formula.variables.contains(&literal.value)
+                && !variables.contains(&&literal.value)
+            {
+                variables.push(&literal.value);
+            }
+        }
+        if variables.len() > TEACHING_MAX_VARIABLES {
+            info!(
+                self,
+                "Not checking {}: over {} variables", clause, TEACHING_MAX_VARIABLES
+            );
+            return None;
+        }
+        variables.sort_by_key(|v| v.key());
+        let bit: HashMap<&V, usize> = variables.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let satisfies = |model: u32, clause: &Clause<V>| {
+            clause
+                .literals
+                .iter()
+                .any(|l| (model >> bit[&l.value] & 1 == 1) != l.negation)
+        };
+
+        let counterexample = (0..1u32 << variables.len()).find(|model| {
+            self.formula.clauses.iter().all(|c| satisfies(*model, c)) && !satisfies(*model, clause)
+        });
+        match counterexample {
+            None => {
+                info!(
+                    self,
+                    "Checked: {} holds in every model of the formula", clause
+                );
+                Some(true)
+            }
+            Some(model) => {
+                let values: Vec<String> = variables
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| format!("{} = {}", v, model >> i & 1 == 1))
+                    .collect();
+                info!(
+                    self,
+                    "ERROR: learned clause {} is NOT entailed by the formula, it fails under {}",
+                    clause,
+                    values.join(", ")
+                );
+                Some(false)
+            }
+        }
+    }
+
+    // A learned unit holds regardless of any decision, so it is fixed at level
+    // 0 straight away rather than waiting for propagation to find it.
+    fn assert_learned_unit(&mut self, id: ClauseId) {
+        let literal = self.clause_ref(id).literals[0].clone();
+        if self.assignments.get(&literal.value).is_none() {
+            trace!(
+                self,
+                "Learned unit, assigning {} = {}",
+                literal.value,
+                !literal.negation
+            );
+            self.assignments
+                .assign(literal.value, !literal.negation, Some(id));
+        }
+        self.learned_units.push(id);
+    }
+
+    // The backjump step: once the backtrack leaves the clause just learned
+    // with a single unassigned literal and the rest false, that literal is
+    // assigned at once, with the clause as its antecedent, rather than
+    // waiting for propagation to find it.
+    fn assert_learned_clause(&mut self, id: ClauseId) {
+        let clause = self.clause_ref(id);
+        let mut unassigned = None;
+        for literal in &clause.literals {
+            match self.literal_value(literal) {
+                Some(false) => {}
+                None if unassigned.is_none() => unassigned = Some(literal.clone()),
+                _ => return,
+            }
+        }
+        if let Some(literal) = unassigned {
+            trace!(
+                self,
+                "Learned clause asserts {} = {}",
+                literal.value,
+                !literal.negation
+            );
+            self.assignments
+                .assign(literal.value, !literal.negation, Some(id));
+        }
+    }
+
+    // Lemmas that do not follow by unit propagation, such as an empty clause
+    // reached under assumptions, are left out of the proof.
+    fn log_proof_step(&mut self, clause: Clause<V>) {
+        self.register_clauses();
+        if let Some(proof) = self.proof.as_mut() {
+            if let Some(hints) = proof::rup_hints(&self.formula.clauses, &clause) {
+                let hints: Vec<usize> = hints
+                    .iter()
+                    .map(|i| self.clause_ids[*i].0 as usize)
+                    .collect();
+                proof.add(self.next_clause_id as usize, &clause, &hints);
+                self.statistics.proof_lemmas += 1;
+                self.statistics.proof_hints += hints.len() as u64;
+            }
+        }
+    }
+
+    pub fn all_variables_assigned(&self) -> bool {
+        self.assignments.assignments.len() == self.formula.variables.len()
+    }
+
+    // Whether the assignments give every clause of the formula, learned ones
+    // aside, a true literal and a value for each of its variables. Clauses
+    // set aside by pure literal elimination are checked too.
+    pub fn verify_model(&self) -> bool {
+        self.formula
+            .clauses
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                !self
+                    .clause_ids
+                    .get(*i)
+                    .is_some_and(|id| self.learned.contains_key(id))
+            })
+            .map(|(_, clause)| clause)
+            .chain(&self.pure_clauses)
+            .all(|clause| {
+                let values: Vec<Option<bool>> = clause
                     .literals
                     .iter()
-                    .filter(|lit| {
-                        self.assignments.get(&lit.value).unwrap().dl == self.assignments.dl
-                    })
-                    .cloned()
+                    .map(|literal| self.literal_value(literal))
                     .collect();
-            } else {
-                // Handle the case where no literal meets the criteria
+                values.iter().all(Option::is_some) && values.contains(&Some(true))
+            })
+    }
+
+    // `all_clauses_satisfied` for the search loop. A clause stays satisfied
+    // until the level of the literal satisfying it is undone, so each check
+    // goes on from where the last one at or below the current level stopped.
+    fn satisfied_so_far(&mut self) -> bool {
+        let level = self.assignments.dl.max(0) as usize;
+        let mut satisfied = self
+            .satisfied_prefix
+            .get(..=level)
+            .unwrap_or(&self.satisfied_prefix)
+            .last()
+            .copied()
+            .unwrap_or(0);
+        while satisfied < self.formula.clauses.len()
+            && matches!(
+                self.clause_status(&self.formula.clauses[satisfied]),
+                ClauseStatus::Satisfied
+            )
+        {
+            satisfied += 1;
+        }
+        self.satisfied_prefix.truncate(level + 1);
+        let last = self.satisfied_prefix.last().copied().unwrap_or(0);
+        self.satisfied_prefix.resize(level + 1, last);
+        self.satisfied_prefix[level] = satisfied;
+        satisfied == self.formula.clauses.len()
+    }
+
+    pub fn all_clauses_satisfied(&self) -> bool {
+        self.formula
+            .clauses
+            .iter()
+            .all(|clause| matches!(self.clause_status(clause), ClauseStatus::Satisfied))
+    }
+
+    // None once every variable is assigned.
+    pub fn pick_branching_variable(&mut self) -> Option<(V, bool)> {
+        let mut unassigned_variables: Vec<&V> = self
+            .formula
+            .variables
+            .iter()
+            .filter(|variable| self.assignments.get(variable).is_none())
+            .collect();
+        unassigned_variables.sort_by_key(|variable| variable.key());
+        let priority = |variable: &V| self.priorities.get(variable).copied().unwrap_or(0);
+        let top = unassigned_variables
+            .iter()
+            .map(|variable| priority(variable))
+            .max()?;
+        unassigned_variables.retain(|variable| priority(variable) == top);
+        let candidates: HashSet<&V> = unassigned_variables.iter().copied().collect();
+
+        let first = (unassigned_variables[0].clone(), false);
+        let (variable, value) = match &self.config.branching {
+            BranchingHeuristic::Random => {
+                let variable = unassigned_variables.choose(&mut self.rng).unwrap();
+                ((*variable).clone(), self.rng.gen())
+            }
+            BranchingHeuristic::FirstUnassigned => first,
+            BranchingHeuristic::JeroslowWang => self
+                .best_literal(&candidates, |clause| {
+                    // 2^-len, by halving as `powi` needs `std`.
+                    (0..clause.literals.len()).fold(1.0, |weight, _| weight / 2.0)
+                })
+                .map_or(first, |literal| (literal.value, !literal.negation)),
+            BranchingHeuristic::Dlis => self
+                .best_literal(&candidates, |_| 1.0)
+                .map_or(first, |literal| (literal.value, !literal.negation)),
+            BranchingHeuristic::StaticOrder(order) => {
+                // The order names the variables as they are displayed.
+                let named: HashMap<String, &V> = candidates
+                    .iter()
+                    .map(|variable| (variable.to_string(), *variable))
+                    .collect();
+                order
+                    .iter()
+                    .find_map(|name| named.get(name))
+                    .map_or(first, |variable| ((*variable).clone(), false))
+            }
+            BranchingHeuristic::Vsids => {
+                let activity = |variable: &V| self.activities.get(variable).copied().unwrap_or(0.0);
+                // The first of the most active, as max_by returns the last.
+                let variable = unassigned_variables
+                    .iter()
+                    .rev()
+                    .max_by(|a, b| activity(a).partial_cmp(&activity(b)).unwrap())
+                    .unwrap();
+                ((*variable).clone(), false)
+            }
+        };
+        let value: bool = match self.saved_phases.get(&variable) {
+            Some(phase) => *phase,
+            None => value,
+        };
+
+        Some((variable, value))
+    }
+
+    // Scores the literals over `candidates` in the clauses not yet satisfied,
+    // ties going to the smallest variable.
+    fn best_literal(
+        &self,
+        candidates: &HashSet<&V>,
+        weight: impl Fn(&Clause<V>) -> f64,
+    ) -> Option<Literal<V>> {
+        let mut scores: HashMap<&Literal<V>, f64> = HashMap::new();
+        for clause in &self.formula.clauses {
+            if matches!(self.clause_status(clause), ClauseStatus::Satisfied) {
+                continue;
+            }
+            let w = weight(clause);
+            for literal in &clause.literals {
+                if candidates.contains(&literal.value) {
+                    *scores.entry(literal).or_insert(0.0) += w;
+                }
+            }
+        }
+        scores
+            .into_iter()
+            .max_by(|(a, a_score), (b, b_score)| {
+                a_score
+                    .total_cmp(b_score)
+                    .then_with(|| (b.value.key(), b.negation).cmp(&(a.value.key(), a.negation)))
+            })
+            .map(|(literal, _)| literal.clone())
+    }
+
+    pub fn level_zero_conflict(&mut self) -> Option<Vec<Clause<V>>> {
+        if self.assignments.dl != 0 {
+            return None;
+        }
+
+        let (reason, clause) = self.propagate();
+        if !matches!(reason, UnitPropagationResult::Conflict) {
+            return None;
+        }
+
+        let explanation = self.explain_conflict(clause.unwrap());
+        let mut chain: Vec<Clause<V>> = explanation
+            .propagations
+            .into_iter()
+            .map(|(_, antecedent)| antecedent)
+            .collect();
+        chain.push(explanation.conflict);
+        Some(chain)
+    }
+
+    fn explain_conflict(&self, conflict: ClauseId) -> UnsatExplanation<V> {
+        let conflict = self.clause_ref(conflict);
+        let mut propagations: Vec<(Literal<V>, Clause<V>)> = Vec::new();
+        let mut visited: HashSet<V> = HashSet::new();
+        self.collect_antecedents(conflict, &mut visited, &mut propagations);
+        UnsatExplanation {
+            propagations,
+            conflict: conflict.clone(),
+        }
+    }
+
+    fn collect_antecedents(
+        &self,
+        clause: &Clause<V>,
+        visited: &mut HashSet<V>,
+        chain: &mut Vec<(Literal<V>, Clause<V>)>,
+    ) {
+        for literal in &clause.literals {
+            if !visited.insert(literal.value.clone()) {
+                continue;
+            }
+            let assignment = match self.assignments.get(&literal.value) {
+                Some(assignment) => assignment,
+                None => continue,
+            };
+            if let Some(id) = assignment.antecedent {
+                let antecedent = self.clause_ref(id);
+                self.collect_antecedents(antecedent, visited, chain);
+                let implied = Literal::new(literal.value.clone(), !assignment.value);
+                chain.push((implied, antecedent.clone()));
+            }
+        }
+    }
+
+    // Undoes the assignments above level `b`, latest first.
+    pub fn backtrack(&mut self, b: i32) {
+        let mut freed: Vec<Literal<V>> = Vec::new();
+        self.satisfied_prefix.truncate((b.max(-1) + 1) as usize);
+        for (variable, assignment) in self.assignments.backtrack(b) {
+            trace!(self, "Backtracking, removing assignment for {}", variable);
+            freed.push(Literal::new(variable.clone(), !assignment.value));
+            self.watch_processed.remove(&variable);
+            // The next decision on the variable picks up where it left off.
+            self.saved_phases.insert(variable, assignment.value);
+        }
+
+        // A watched clause skipped because one watch was true can become unit
+        // once that watch is unassigned, if its other watch stays false. This
+        // happens after chronological backtracking, where a literal implied
+        // below the current level is still assigned at it. Such false watches
+        // are looked at again.
+        for literal in freed {
+            for i in self.watches.get(&literal).cloned().unwrap_or_default() {
+                if let Some((first, second)) = &self.watched_literals[i] {
+                    let other = if *first == literal { second } else { first };
+                    if self.literal_value(other) == Some(false) {
+                        self.watch_processed.remove(&other.value);
+                    }
+                }
+            }
+        }
+    }
+
+    // The resolvent of `a` and `b` on `x`, or None when another variable
+    // occurs in both polarities, making it a tautology not worth learning.
+    pub fn resolve<Q>(&self, a: &Clause<V>, b: &Clause<V>, x: &Q) -> Option<Clause<V>>
+    where
+        V: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let mut result: HashSet<Literal<V>> = a.literals.iter().cloned().collect();
+        result.extend(b.literals.iter().cloned());
+        result.retain(|literal| literal.value.borrow() != x);
+        let mut literals: Vec<Literal<V>> = result.into_iter().collect();
+        literals.sort_by(|a, b| (a.value.key(), a.negation).cmp(&(b.value.key(), b.negation)));
+        let resolvent = Clause::new(literals);
+        if resolvent.is_tautology() {
+            return None;
+        }
+        Some(resolvent)
+    }
+
+    pub fn conflict_analysis(&self, clause: &Clause<V>) -> (i32, Option<Clause<V>>) {
+        if self.assignments.dl == 0 {
+            return (-1, None);
+        }
+
+        // A literal left unassigned, which no clause on the trail should hold,
+        // counts as being at no level rather than panicking.
+        let level = |lit: &Literal<V>| self.assignments.get(&lit.value).map(|a| a.dl);
+        // First UIP: the literals of the current level are resolved away in
+        // reverse trail order, the latest first, until a single one is left.
+        let mut current_clause = clause.clone();
+        loop {
+            let at_level: Vec<(&Literal<V>, &Assignment)> = current_clause
+                .literals
+                .iter()
+                .filter_map(|lit| self.assignments.get(&lit.value).map(|a| (lit, a)))
+                .filter(|(_, a)| a.dl == self.assignments.dl)
+                .collect();
+            if at_level.len() <= 1 {
                 break;
             }
+            let (latest, assignment) = at_level.iter().max_by_key(|(_, a)| a.order).unwrap();
+            // A tautological resolvent only comes from an inconsistent trail,
+            // and the clause so far, while not asserting, still follows from
+            // the formula. So does one whose latest literal is a decision.
+            let resolvent = assignment
+                .antecedent
+                .and_then(|id| self.resolve(&current_clause, self.clause_ref(id), &latest.value));
+            match resolvent {
+                Some(resolvent) => current_clause = resolvent,
+                None => break,
+            }
         }
 
         let mut decision_levels: Vec<i32> = current_clause
             .literals
             .iter()
-            .map(|lit| self.assignments.get(&lit.value).unwrap().dl)
+            .filter_map(level)
             .collect::<HashSet<i32>>()
             .into_iter()
             .collect();
@@ -308,3 +2323,1368 @@ impl CdclSolver {
         }
     }
 }
+
+// The i-th term, from 1, of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, 1, 2,
+// ... Its terms grow without bound, so restarting after multiples of them
+// leaves the search complete.
+pub fn luby(i: u64) -> u64 {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if (1 << k) - 1 == i {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solver(text: &str) -> CdclSolver {
+        let mut solver = CdclSolver::new(crate::parse_text_cnf(text).unwrap());
+        solver.set_output(sink());
+        solver
+    }
+
+    fn literal(text: &str) -> Literal {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn assumptions_do_not_outlive_an_unsat_solve() {
+        let mut solver = solver("-1\n1 2\n");
+        assert_eq!(
+            solver.solve_under_assumptions(&[literal("1")]),
+            SolverResult::Unresolved
+        );
+        assert_eq!(solver.unsat_core(), vec![literal("1")]);
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Satisfied);
+        assert!(solver.verify_model());
+        assert!(solver.unsat_core().is_empty());
+    }
+
+    #[test]
+    fn assumptions_do_not_outlive_a_relation() {
+        let mut solver = solver("-a b\n-b a\n-c\n");
+        assert_eq!(
+            solver.relation(&literal("a"), &literal("b")).unwrap(),
+            LiteralRelation::Equivalent
+        );
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Satisfied);
+        assert!(solver.verify_model());
+    }
+
+    // 34 clauses of 3 literals over variables 1 to 8, one per line, about
+    // as likely SAT as not.
+    fn random_text_cnf(rng: &mut StdRng) -> String {
+        (0..34)
+            .map(|_| {
+                let literals: Vec<String> = (0..3)
+                    .map(|_| {
+                        let variable = rng.gen_range(1..=8);
+                        if rng.gen() {
+                            format!("-{}", variable)
+                        } else {
+                            variable.to_string()
+                        }
+                    })
+                    .collect();
+                literals.join(" ") + "\n"
+            })
+            .collect()
+    }
+
+    // `clauses` clauses of 3 literals over variables 1 to `variables`.
+    fn random_3cnf(rng: &mut StdRng, variables: u32, clauses: usize) -> Formula<u32> {
+        let clauses = (0..clauses)
+            .map(|_| {
+                let literals = (0..3)
+                    .map(|_| Literal::new(rng.gen_range(1..=variables), rng.gen()))
+                    .collect();
+                Clause::new(literals)
+            })
+            .collect();
+        Formula::new(clauses)
+    }
+
+    #[test]
+    fn is_satisfiable_agrees_with_solve() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut outcomes = HashSet::new();
+        for _ in 0..200 {
+            let text = random_text_cnf(&mut rng);
+            let mut full = solver(&text);
+            full.solve();
+            let mut quick = solver(&text);
+            let satisfiable = quick.is_satisfiable();
+            assert_eq!(satisfiable, *full.sat() == SolverResult::Satisfied);
+            if satisfiable {
+                assert!(quick.all_clauses_satisfied());
+            }
+            outcomes.insert(satisfiable);
+        }
+        assert_eq!(outcomes.len(), 2);
+    }
+
+    #[test]
+    fn numbered_and_named_variables_agree() {
+        let mut rng = StdRng::seed_from_u64(272);
+        for _ in 0..100 {
+            let text = random_text_cnf(&mut rng);
+            let dimacs = format!("p cnf 8 34\n{}", text.replace('\n', " 0\n"));
+            let mut numbered: CdclSolver<u32> =
+                CdclSolver::new(crate::parse_dimacs_cnf(&dimacs).unwrap());
+            numbered.set_output(sink());
+            numbered.solve();
+            let mut named = solver(&text);
+            named.solve();
+            assert_eq!(numbered.sat(), named.sat());
+            if *numbered.sat() == SolverResult::Satisfied {
+                assert!(numbered.verify_model());
+                let model = numbered.model().unwrap();
+                assert_eq!(model.values.len(), 8);
+                assert_eq!(numbered.value_of(&1), model.values.get(&1).copied());
+            }
+        }
+    }
+
+    #[test]
+    fn added_satisfied_clause_extends_the_model() {
+        let mut solver = solver("a\n");
+        solver.solve();
+        solver.add_clause(Clause::new(vec![literal("a"), literal("b")]));
+        assert_eq!(*solver.sat(), SolverResult::Satisfied);
+        assert!(solver.assignments().get(&String::from("b")).is_some());
+        assert!(solver.verify_model());
+    }
+
+    #[test]
+    fn fixed_literals_include_units_and_pure_literals() {
+        let text = "a\n-a b\nc d\n-c e\n";
+        let fixed = |config: SolverConfig| {
+            let formula = crate::parse_text_cnf(text).unwrap();
+            let mut solver = CdclSolver::with_config(formula, config);
+            solver.solve();
+            solver.fixed_literals()
+        };
+        let units = vec![literal("a"), literal("b")];
+        assert_eq!(fixed(SolverConfig::default()), units);
+        assert_eq!(fixed(SolverConfig::default().decompose(true)), units);
+        let with_pure = fixed(SolverConfig::default().pure_literals(true));
+        assert_eq!(
+            with_pure,
+            vec![literal("a"), literal("b"), literal("d"), literal("e")]
+        );
+    }
+
+    #[test]
+    fn value_of_agrees_with_model() {
+        for policy in [
+            FreePolicy::AllTrue,
+            FreePolicy::AllFalse,
+            FreePolicy::Omit,
+            FreePolicy::Mark,
+        ] {
+            let mut formula = crate::parse_text_cnf("a b\n-a c\n").unwrap();
+            formula.variables.insert(String::from("d"));
+            let config = SolverConfig::default().free_variable_policy(policy);
+            let mut solver = CdclSolver::with_config(formula, config);
+            solver.set_output(sink());
+            assert!(solver.is_satisfiable());
+            let model = solver.model().unwrap();
+            for variable in ["a", "b", "c", "d"] {
+                assert_eq!(
+                    solver.value_of(variable),
+                    model.values.get(variable).copied()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn model_under_assumptions_holds_them() {
+        let mut solver = solver("a b\n-a c\n");
+        let assumptions = [literal("a"), literal("¬b")];
+        assert_eq!(
+            solver.solve_under_assumptions(&assumptions),
+            SolverResult::Satisfied
+        );
+        let model = solver.model().unwrap();
+        assert!(model.values["a"]);
+        assert!(!model.values["b"]);
+        assert!(model.values["c"]);
+    }
+
+    #[test]
+    fn unit_clauses_pairs_each_with_its_literal() {
+        let mut solver = solver("a b c\n-a d\n");
+        solver.decide(&literal("¬b"));
+        solver.decide(&literal("¬c"));
+        let units = solver.unit_clauses();
+        assert_eq!(units.len(), 1);
+        assert!(std::ptr::eq(units[0].0, &solver.formula.clauses[0]));
+        assert_eq!(units[0].1, literal("a"));
+    }
+
+    #[test]
+    fn level_zero_conflict_lists_the_chain() {
+        let mut solver = solver("a\n-a b\n-b\n");
+        let mut chain: Vec<String> = solver
+            .level_zero_conflict()
+            .unwrap()
+            .iter()
+            .map(|clause| clause.to_string())
+            .collect();
+        chain.sort();
+        assert_eq!(chain, ["a", "¬a ∨ b", "¬b"]);
+    }
+
+    #[test]
+    fn config_settings_take_effect() {
+        let config = SolverConfig::default().branching(BranchingHeuristic::FirstUnassigned);
+        let formula = crate::parse_text_cnf("1 2\n3 4\n").unwrap();
+        let mut solver = CdclSolver::with_config(formula, config);
+        assert_eq!(
+            solver.config().branching,
+            BranchingHeuristic::FirstUnassigned
+        );
+        solver.solve();
+        // The smallest unassigned variable is always decided false first.
+        let model = solver.model().unwrap();
+        for (variable, value) in [("1", false), ("2", true), ("3", false), ("4", true)] {
+            assert_eq!(model.values[variable], value);
+        }
+    }
+
+    #[test]
+    fn clause_ids_are_stable_and_name_antecedents() {
+        let mut solver = solver("a b\n-a c\n-c d\n");
+        let ids = solver.clause_ids().to_vec();
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+        let clauses: Vec<String> = ids
+            .iter()
+            .map(|id| solver.clause(*id).unwrap().to_string())
+            .collect();
+
+        solver.decide(&literal("a"));
+        solver.propagate_only();
+        let antecedent = |solver: &CdclSolver, variable: &str| {
+            let id = solver
+                .assignments()
+                .get(&variable.to_string())
+                .unwrap()
+                .antecedent;
+            solver.clause(id.unwrap()).unwrap().to_string()
+        };
+        assert_eq!(antecedent(&solver, "c"), clauses[1]);
+        assert_eq!(antecedent(&solver, "d"), clauses[2]);
+
+        solver.backjump(0);
+        assert_eq!(solver.clause_ids(), ids);
+        for (id, clause) in ids.iter().zip(&clauses) {
+            assert_eq!(solver.clause(*id).unwrap().to_string(), *clause);
+        }
+    }
+
+    #[test]
+    fn sat_certificate_names_a_true_literal_per_clause() {
+        let mut solver = solver("a b\n-a c\n-b -c\n");
+        solver.solve();
+        let model = solver.model().unwrap();
+        let certificate = solver.sat_certificate();
+        let (_, clauses) = certificate.split_once("Clauses:\n").unwrap();
+        let lines: Vec<&str> = clauses.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for (line, clause) in lines.iter().zip(&solver.formula.clauses) {
+            let (named, witness) = line.split_once(" is satisfied by ").unwrap();
+            assert_eq!(named, clause.to_string());
+            let witness = literal(witness);
+            assert!(clause.literals.contains(&witness));
+            assert_eq!(model.values[&witness.value], !witness.negation);
+        }
+    }
+
+    #[test]
+    fn duplicate_learned_clauses_are_added_once() {
+        let mut solver = solver("a b c\n-a -b\n");
+        let before = solver.formula.clauses.len();
+        let first =
+            solver.add_learned_clause(crate::parse_text_cnf("-c a\n").unwrap().clauses.remove(0));
+        let again =
+            solver.add_learned_clause(crate::parse_text_cnf("a -c a\n").unwrap().clauses.remove(0));
+        assert_eq!(first, again);
+        assert_eq!(solver.formula.clauses.len(), before + 1);
+        assert_eq!(solver.statistics().duplicate_learned_clauses, 1);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_propagation_matches_the_serial_scan() {
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(206);
+        for i in 0..300 {
+            let n: u32 = rng.gen_range(2..12);
+            let random_clause = |rng: &mut StdRng| {
+                let literals = (0..rng.gen_range(1..5))
+                    .map(|_| Literal::new(rng.gen_range(1..=n), rng.gen()))
+                    .collect();
+                Clause::new(literals)
+            };
+            let clauses: Vec<Clause<u32>> = (0..rng.gen_range(1..n * 4))
+                .map(|_| random_clause(&mut rng))
+                .collect();
+            let learned: Vec<Clause<u32>> = (0..rng.gen_range(0..4))
+                .map(|_| random_clause(&mut rng))
+                .collect();
+            let decisions: Vec<Literal<u32>> = (0..rng.gen_range(0..3))
+                .map(|_| Literal::new(rng.gen_range(1..=n), rng.gen()))
+                .collect();
+            let config = SolverConfig::default()
+                .watch_threshold([None, Some(2)][i % 2])
+                .learned_first(i % 4 >= 2);
+
+            let propagate = |parallel: bool| {
+                let mut solver =
+                    CdclSolver::with_config(Formula::new(clauses.clone()), config.clone());
+                for clause in &learned {
+                    solver.add_learned_clause(clause.clone());
+                }
+                for decision in &decisions {
+                    if solver.assignments.get(&decision.value).is_none() {
+                        solver.decide(decision);
+                    }
+                }
+                let (result, _) = if parallel {
+                    solver.parallel_unit_propagation()
+                } else {
+                    solver.unit_propagation()
+                };
+                let conflict = matches!(result, UnitPropagationResult::Conflict);
+                (conflict, solver.current_values())
+            };
+            let (serial_conflict, serial) = propagate(false);
+            let (parallel_conflict, parallel) = propagate(true);
+            assert_eq!(serial_conflict, parallel_conflict);
+            if !serial_conflict {
+                assert_eq!(serial, parallel);
+            }
+        }
+    }
+
+    #[test]
+    fn unsat_cores_are_unsat_subsets_of_the_assumptions() {
+        use crate::reference::brute_force_sat;
+        use rand::rngs::StdRng;
+        use rand::Rng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(264);
+        let mut cores = 0;
+        for _ in 0..300 {
+            let n: u32 = rng.gen_range(3..9);
+            let clauses: Vec<Clause<u32>> = (0..rng.gen_range(1..n * 3))
+                .map(|_| {
+                    let literals = (0..rng.gen_range(1..4))
+                        .map(|_| Literal::new(rng.gen_range(1..=n), rng.gen()))
+                        .collect();
+                    Clause::new(literals)
+                })
+                .collect();
+            let mut assumptions: Vec<Literal<u32>> = Vec::new();
+            for _ in 0..rng.gen_range(1..5) {
+                let literal = Literal::new(rng.gen_range(1..=n), rng.gen());
+                if !assumptions.iter().any(|a| a.value == literal.value) {
+                    assumptions.push(literal);
+                }
+            }
+            let formula = Formula::new(clauses);
+            let mut solver = CdclSolver::new(formula.clone());
+            solver.set_output(sink());
+            if solver.solve_under_assumptions(&assumptions) == SolverResult::Satisfied {
+                continue;
+            }
+            let core = solver.unsat_core();
+            if brute_force_sat(&formula) == SolverResult::Unresolved {
+                assert!(core.is_empty());
+                continue;
+            }
+            cores += 1;
+            assert!(core.iter().all(|literal| assumptions.contains(literal)));
+            let mut with_core = formula.clauses.clone();
+            with_core.extend(
+                core.iter()
+                    .map(|literal| Clause::new(vec![literal.clone()])),
+            );
+            assert_eq!(
+                brute_force_sat(&Formula::new(with_core)),
+                SolverResult::Unresolved,
+                "{} with core {:?}",
+                formula,
+                core
+            );
+        }
+        assert!(cores > 20);
+    }
+
+    #[test]
+    fn assumptions_propagate_without_changing_the_solver() {
+        let mut solver = solver("-a b\n-b c\n-c -d\ne f\n");
+        assert_eq!(
+            solver.propagate_assumptions(&[literal("a")]).ok(),
+            Some(vec![literal("b"), literal("c"), literal("¬d")])
+        );
+        assert!(solver.assignments().assignments.is_empty());
+        assert_eq!(solver.assignments().dl, 0);
+
+        // The assumptions falsify a clause of the formula.
+        let conflict = solver
+            .propagate_assumptions(&[literal("b"), literal("d")])
+            .unwrap_err();
+        assert_eq!(conflict.to_string(), "¬c ∨ ¬d");
+        // Or one of them is already false.
+        assert_eq!(
+            solver
+                .propagate_assumptions(&[literal("e"), literal("¬e")])
+                .unwrap_err()
+                .to_string(),
+            "e"
+        );
+        assert!(solver.assignments().assignments.is_empty());
+        assert_eq!(solver.statistics().propagations, 0);
+    }
+
+    #[test]
+    fn relations_between_literals() {
+        // a and b are equivalent, c is the opposite of a, and d is free.
+        let mut solver = solver("-a b\n-b a\na c\n-a -c\nd e\n");
+        assert_eq!(
+            solver.relation(&literal("a"), &literal("b")),
+            Ok(LiteralRelation::Equivalent)
+        );
+        assert_eq!(
+            solver.relation(&literal("a"), &literal("¬b")),
+            Ok(LiteralRelation::Opposite)
+        );
+        assert_eq!(
+            solver.relation(&literal("b"), &literal("c")),
+            Ok(LiteralRelation::Opposite)
+        );
+        assert_eq!(
+            solver.relation(&literal("¬c"), &literal("a")),
+            Ok(LiteralRelation::Equivalent)
+        );
+        assert_eq!(
+            solver.relation(&literal("a"), &literal("d")),
+            Ok(LiteralRelation::Independent)
+        );
+        assert_eq!(
+            solver.relation(&literal("a"), &literal("x")),
+            Err(Error::UnknownVariable(String::from("x")))
+        );
+        assert_eq!(
+            solver.relation(&literal("x"), &literal("a")),
+            Err(Error::UnknownVariable(String::from("x")))
+        );
+        solver.solve();
+        assert!(solver.verify_model());
+    }
+
+    #[test]
+    fn clause_db_verifies_after_additions_and_deletions() {
+        let mut rng = StdRng::seed_from_u64(260);
+        let formula = random_3cnf(&mut rng, 40, 180);
+        let config = SolverConfig::default()
+            .seed(Some(260))
+            .watch_threshold(Some(2))
+            .reduce_interval(Some(5));
+        let mut solver = CdclSolver::with_config(formula, config);
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+        solver.solve();
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+
+        let learned = solver.learned.len();
+        assert!(learned > 1);
+        solver.reduce_learned(None);
+        assert!(solver.learned.len() < learned);
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+
+        solver.add_clause(Clause::new(vec![
+            Literal::new(1, false),
+            Literal::new(41, true),
+        ]));
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+        solver.add_clause(Clause::new(vec![Literal::new(2, true)]));
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+        solver.add_learned_clause(Clause::new(vec![
+            Literal::new(3, false),
+            Literal::new(4, false),
+        ]));
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+
+        solver.preprocess();
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+        solver.solve();
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+    }
+
+    #[test]
+    fn the_empty_clause_is_unsat_without_search() {
+        let mut formula = crate::parse_text_cnf("1 2\n-1 2\n1 -2\n").unwrap();
+        formula.clauses.insert(1, Clause::new(Vec::new()));
+        let mut solver = CdclSolver::new(formula);
+        solver.set_output(sink());
+        solver.enable_proof(ProofFormat::Rup);
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Unresolved);
+        assert!(solver.model().is_none());
+        assert_eq!(solver.statistics().decisions, 0);
+        assert_eq!(solver.statistics().conflicts, 0);
+        assert!(solver.assignments().assignments.is_empty());
+        let explanation = solver.unsat_explanation().unwrap();
+        assert!(explanation.conflict.literals.is_empty());
+        assert!(explanation.propagations.is_empty());
+        assert_eq!(solver.proof().unwrap(), "0");
+    }
+
+    #[test]
+    fn the_empty_formula_is_sat_with_an_empty_model() {
+        for formula in [Formula::new(Vec::new()), crate::parse_text_cnf("").unwrap()] {
+            let mut solver = CdclSolver::new(formula);
+            solver.set_output(sink());
+            solver.solve();
+            assert_eq!(*solver.sat(), SolverResult::Satisfied);
+            assert!(solver.verify_model());
+            let model = solver.model().unwrap();
+            assert!(model.values.is_empty());
+            assert!(model.free.is_empty());
+            assert_eq!(solver.statistics().decisions, 0);
+        }
+
+        // Declared variables still get values from the free variable policy.
+        let formula = crate::parse_dimacs_cnf("p cnf 2 0\n").unwrap();
+        let config = SolverConfig::default().free_variable_policy(FreePolicy::AllFalse);
+        let mut solver = CdclSolver::with_config(formula, config);
+        solver.set_output(sink());
+        assert!(solver.is_satisfiable());
+        let model = solver.model().unwrap();
+        assert_eq!(model.values.len(), 2);
+        assert!(model.values.values().all(|value| !value));
+    }
+
+    #[test]
+    fn conflict_analysis_skips_unassigned_literals() {
+        let mut solver = solver("1 2\n1 -2\n3 4\n");
+        solver.decide(&literal("¬1"));
+        let (reason, clause) = solver.propagate();
+        assert!(matches!(reason, UnitPropagationResult::Conflict));
+        let conflict = solver.clause_ref(clause.unwrap()).clone();
+
+        // `9` is in no clause and has no value.
+        let mut stray = conflict.literals.clone();
+        stray.push(literal("9"));
+        let (level, learned) = solver.conflict_analysis(&Clause::new(stray));
+        assert_eq!(level, 0);
+        assert_eq!(learned.unwrap().to_string(), "1 ∨ 9");
+
+        let (level, learned) = solver.conflict_analysis(&Clause::new(vec![literal("9")]));
+        assert_eq!(level, 0);
+        assert_eq!(learned.unwrap().to_string(), "9");
+    }
+
+    #[test]
+    fn learned_clauses_have_one_literal_at_the_conflict_level() {
+        let mut rng = StdRng::seed_from_u64(286);
+        let mut analyzed = 0;
+        for _ in 0..40 {
+            let mut solver = CdclSolver::new(random_3cnf(&mut rng, 30, 130));
+            solver.set_output(sink());
+            let (reason, _) = solver.propagate();
+            if matches!(reason, UnitPropagationResult::Conflict) {
+                continue;
+            }
+            let conflict = loop {
+                let free = (1..=30).find(|v| solver.assignments.get(v).is_none());
+                let Some(variable) = free else {
+                    break None;
+                };
+                solver.decide(&Literal::new(variable, rng.gen()));
+                let (reason, clause) = solver.propagate();
+                if matches!(reason, UnitPropagationResult::Conflict) {
+                    break clause;
+                }
+            };
+            let Some(conflict) = conflict else {
+                continue;
+            };
+
+            let conflict = solver.clause_ref(conflict).clone();
+            let (level, learned) = solver.conflict_analysis(&conflict);
+            let learned = learned.unwrap();
+            let levels: Vec<i32> = learned
+                .literals
+                .iter()
+                .map(|l| {
+                    let assignment = solver.assignments.get(&l.value).unwrap();
+                    assert_eq!(assignment.value, l.negation, "{} is not falsified", learned);
+                    assignment.dl
+                })
+                .collect();
+            let dl = solver.assignments.dl;
+            assert_eq!(
+                levels.iter().filter(|l| **l == dl).count(),
+                1,
+                "{}",
+                learned
+            );
+            let below = levels.iter().filter(|l| **l < dl).max();
+            assert_eq!(level, *below.unwrap_or(&0));
+            analyzed += 1;
+        }
+        assert!(analyzed > 20);
+    }
+
+    #[test]
+    fn core_hints_give_the_result_of_a_full_solve() {
+        let text = "1 2\n-1 2\n1 -2\n-1 -2\n3 4\n-3 5\n";
+        let mut plain = solver(text);
+        plain.solve();
+        assert_eq!(*plain.sat(), SolverResult::Unresolved);
+
+        let muses = crate::mus::all_muses(&plain.formula);
+        assert!(!muses.is_empty());
+        for core in &muses {
+            let mut hinted = solver(text);
+            hinted.solve_with_core_hint(core);
+            assert_eq!(*hinted.sat(), SolverResult::Unresolved);
+            // Solving again, hinted or not, does not change the answer.
+            hinted.solve_with_core_hint(core);
+            assert_eq!(*hinted.sat(), SolverResult::Unresolved);
+            hinted.solve();
+            assert_eq!(*hinted.sat(), SolverResult::Unresolved);
+        }
+
+        // A core that no longer applies, because a clause of it is gone or it
+        // is satisfiable, falls back to solving in full.
+        let text = "1 2\n-1 2\n1 -2\n3 4\n-3 5\n";
+        let satisfiable = vec![
+            Clause::new(vec![literal("1"), literal("2")]),
+            Clause::new(vec![literal("¬1"), literal("2")]),
+        ];
+        for core in [muses[0].clone(), satisfiable] {
+            let mut hinted = solver(text);
+            hinted.solve_with_core_hint(&core);
+            assert_eq!(*hinted.sat(), SolverResult::Satisfied);
+            assert!(hinted.verify_model());
+            hinted.solve_with_core_hint(&core);
+            assert_eq!(*hinted.sat(), SolverResult::Satisfied);
+            assert!(hinted.verify_model());
+        }
+    }
+
+    // Pigeon i sits in hole j when `p{i}_{j}` holds, and no two share a hole.
+    fn pigeonhole(pigeons: usize, holes: usize) -> CdclSolver {
+        let mut text = String::new();
+        for i in 0..pigeons {
+            let holes: Vec<String> = (0..holes).map(|j| format!("p{}_{}", i, j)).collect();
+            text.push_str(&format!("{}\n", holes.join(" ")));
+        }
+        for j in 0..holes {
+            for a in 0..pigeons {
+                for b in a + 1..pigeons {
+                    text.push_str(&format!("-p{}_{} -p{}_{}\n", a, j, b, j));
+                }
+            }
+        }
+        let mut solver = solver(&text);
+        solver.config = solver.config.clone().seed(Some(228));
+        solver
+    }
+
+    #[test]
+    fn conflict_limits_resume_the_search() {
+        let mut full = pigeonhole(6, 5);
+        full.solve();
+        assert_eq!(*full.sat(), SolverResult::Unresolved);
+
+        let mut limited = pigeonhole(6, 5);
+        let mut calls = 0;
+        let result = loop {
+            let before = limited.statistics().conflicts;
+            let result = limited.solve_with_limit(10);
+            calls += 1;
+            if let Some(result) = result {
+                break result;
+            }
+            assert!(limited.statistics().conflicts - before > 10);
+            assert!(limited.verify_clause_db().is_ok());
+        };
+        assert!(calls > 1);
+        assert_eq!(result, SolverResult::Unresolved);
+
+        let mut limited = pigeonhole(5, 5);
+        while limited.solve_with_limit(1).is_none() {}
+        assert_eq!(*limited.sat(), SolverResult::Satisfied);
+        assert!(limited.verify_model());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn timeouts_report_progress_and_resume() {
+        let mut solver = pigeonhole(6, 5);
+        let variables = solver.formula.variables.len();
+        let progress = solver.solve_with_timeout(Duration::ZERO).err().unwrap();
+        assert!(progress.assigned_variables <= variables);
+        assert_eq!(progress.max_decision_level, 1);
+        assert_eq!(solver.statistics().decisions, 1);
+
+        let mut timeouts = 1;
+        let result = loop {
+            let decisions = solver.statistics().decisions;
+            match solver.solve_with_timeout(Duration::ZERO) {
+                Ok(result) => break result,
+                Err(progress) => {
+                    assert!(solver.statistics().decisions > decisions);
+                    assert!(progress.assigned_variables <= variables);
+                    assert!(progress.max_decision_level >= solver.assignments().dl);
+                    timeouts += 1;
+                }
+            }
+        };
+        assert!(timeouts > 1);
+        assert_eq!(result, SolverResult::Unresolved);
+    }
+
+    #[test]
+    fn propagate_only_reaches_a_fixpoint_without_deciding() {
+        let mut solver = solver("1\n-1 2\n-2 3\n-3 -4 5\n6 7\n-5 -6\n-5 -7\n");
+        let fixpoint = |solver: &CdclSolver| {
+            solver
+                .formula
+                .clauses
+                .iter()
+                .all(|clause| !matches!(solver.clause_status(clause), ClauseStatus::Unit))
+        };
+        let (result, clause) = solver.propagate_only();
+        assert!(matches!(result, UnitPropagationResult::Unresolved));
+        assert!(clause.is_none());
+        assert!(fixpoint(&solver));
+        assert_eq!(solver.assignments().dl, 0);
+        assert_eq!(solver.statistics().decisions, 0);
+        assert_eq!(solver.literal_value(&literal("3")), Some(true));
+        assert_eq!(solver.literal_value(&literal("4")), None);
+        let trail: Vec<String> = solver
+            .assignments()
+            .trail()
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(trail, ["1", "2", "3"]);
+
+        // Once at the fixpoint, propagating again changes nothing.
+        solver.propagate_only();
+        assert_eq!(solver.assignments().trail().len(), 3);
+
+        solver.decide(&literal("4"));
+        let (result, clause) = solver.propagate_only();
+        assert!(matches!(result, UnitPropagationResult::Conflict));
+        assert_eq!(clause.unwrap().to_string(), "6 ∨ 7");
+        assert_eq!(solver.assignments().dl, 1);
+        assert_eq!(solver.statistics().decisions, 1);
+        assert!(solver.learned.is_empty());
+        assert_eq!(solver.literal_value(&literal("5")), Some(true));
+        assert_eq!(solver.literal_value(&literal("6")), Some(false));
+        assert_eq!(solver.literal_value(&literal("7")), Some(false));
+    }
+
+    #[test]
+    fn invariants_hold_throughout_the_search() {
+        let mut rng = StdRng::seed_from_u64(239);
+        for seed in 0..20 {
+            let formula = random_3cnf(&mut rng, 20, 90);
+            let config = SolverConfig::default()
+                .seed(Some(seed))
+                .check_invariants(true)
+                .chrono_threshold(if seed % 2 == 0 { Some(1) } else { None })
+                .restart_base(Some(2));
+            let mut solver = CdclSolver::with_config(formula, config);
+            solver.solve();
+            solver.check_invariants();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "level 1 holds 2 decisions")]
+    fn invariants_catch_two_decisions_at_a_level() {
+        let mut solver = solver("1 2\n3 4\n");
+        solver.decide(&literal("1"));
+        solver.assignments.assign(String::from("3"), true, None);
+        solver.check_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "¬1 is not falsified below 2 in its antecedent")]
+    fn invariants_catch_an_antecedent_that_does_not_imply() {
+        let mut solver = solver("-1 2\n3 4\n");
+        let id = solver.clause_ids[0];
+        solver.assignments.assign(String::from("2"), true, Some(id));
+        solver.check_invariants();
+    }
+
+    #[test]
+    fn chronological_backtracking_gives_the_same_results() {
+        let mut rng = StdRng::seed_from_u64(230);
+        let mut outcomes = HashSet::new();
+        // Whether backtracking chronologically ever changed the search.
+        let mut changed = false;
+        for seed in 0..60 {
+            let formula = random_3cnf(&mut rng, 25, 107);
+            let mut results = Vec::new();
+            let mut conflicts = Vec::new();
+            for threshold in [None, Some(0), Some(1), Some(3)] {
+                let config = SolverConfig::default()
+                    .seed(Some(seed))
+                    .check_invariants(true)
+                    .chrono_threshold(threshold);
+                let mut solver = CdclSolver::with_config(formula.clone(), config);
+                solver.set_output(sink());
+                solver.solve();
+                if *solver.sat() == SolverResult::Satisfied {
+                    assert!(solver.verify_model());
+                }
+                results.push(*solver.sat());
+                conflicts.push(solver.statistics().conflicts);
+            }
+            assert!(results.iter().all(|result| *result == results[0]));
+            changed |= conflicts.iter().any(|count| *count != conflicts[0]);
+            outcomes.insert(results[0] == SolverResult::Satisfied);
+        }
+        assert_eq!(outcomes.len(), 2);
+        assert!(changed);
+    }
+
+    #[test]
+    fn learned_first_scans_and_propagates_learned_clauses_first() {
+        for learned_first in [false, true] {
+            let mut solver = solver("-5\n-1 2\n3 4\n");
+            solver.config = solver.config.clone().learned_first(learned_first);
+            let older = solver.add_learned_clause(Clause::new(vec![
+                literal("¬1"),
+                literal("5"),
+                literal("2"),
+            ]));
+            let newer = solver.add_learned_clause(Clause::new(vec![literal("¬3"), literal("5")]));
+            let order: Vec<ClauseId> = solver
+                .scan_order()
+                .iter()
+                .map(|i| solver.clause_ids[*i])
+                .collect();
+            let original = solver.clause_ids[..3].to_vec();
+            if learned_first {
+                assert_eq!(order[..2], [newer, older]);
+                assert_eq!(order[2..], *original);
+            } else {
+                assert_eq!(order[..3], *original);
+                assert_eq!(order[3..], [older, newer]);
+            }
+
+            // Both clauses imply 2 once 1 is set; the first one scanned is
+            // its reason.
+            solver.propagate();
+            solver.decide(&literal("1"));
+            solver.propagate();
+            let antecedent = solver
+                .assignments
+                .get(&String::from("2"))
+                .unwrap()
+                .antecedent;
+            let expected = if learned_first { older } else { original[1] };
+            assert_eq!(antecedent, Some(expected));
+        }
+    }
+
+    #[test]
+    fn rephasing_keeps_the_longest_conflict_free_trail() {
+        let mut rng = StdRng::seed_from_u64(234);
+        let mut rephased = false;
+        for seed in 0..30 {
+            let formula = random_3cnf(&mut rng, 25, 107);
+            let mut plain =
+                CdclSolver::with_config(formula.clone(), SolverConfig::default().seed(Some(seed)));
+            plain.solve();
+            assert!(plain.best_trail().is_empty());
+
+            let config = SolverConfig::default()
+                .seed(Some(seed))
+                .rephase_interval(Some(2));
+            let mut solver = CdclSolver::with_config(formula, config);
+            solver.solve();
+            assert_eq!(solver.sat(), plain.sat());
+            if solver.statistics().conflicts < 2 {
+                continue;
+            }
+            rephased = true;
+
+            // No original clause is false under the best trail. Clauses
+            // learned after it was recorded can be.
+            let best = solver.best_trail().clone();
+            assert!(!best.is_empty());
+            let original = solver
+                .formula
+                .clauses
+                .iter()
+                .zip(&solver.clause_ids)
+                .filter(|(_, id)| !solver.learned.contains_key(*id))
+                .map(|(clause, _)| clause);
+            for clause in original {
+                assert!(clause
+                    .literals
+                    .iter()
+                    .any(|l| best.get(&l.value) != Some(&l.negation)));
+            }
+            solver.rephase();
+            for (variable, value) in &best {
+                assert_eq!(solver.saved_phases()[variable], *value);
+            }
+        }
+        assert!(rephased);
+    }
+
+    // The variables decided by a full solve, in order, with their values.
+    fn decisions(solver: &mut CdclSolver) -> Vec<(String, bool)> {
+        solver.enable_search_tree();
+        solver.solve();
+        assert!(solver.verify_model());
+        let tree = solver.search_tree().unwrap();
+        tree.nodes
+            .iter()
+            .map(|node| (node.variable.clone(), node.value))
+            .collect()
+    }
+
+    #[test]
+    fn priorities_come_before_activity() {
+        // `d` is the most active variable, and `a` and `b` the least.
+        let text = "-a -d\n-b -d\n-c -d\n-d -e\n-c -e\n";
+        let mut plain = solver(text);
+        plain.config = plain.config.clone().branching(BranchingHeuristic::Vsids);
+        assert_eq!(decisions(&mut plain)[0].0, "d");
+
+        let mut prioritized = solver(text);
+        prioritized.config = prioritized
+            .config
+            .clone()
+            .branching(BranchingHeuristic::Vsids);
+        prioritized.set_branching_priority("a", 1).unwrap();
+        prioritized.set_branching_priority("b", 2).unwrap();
+        let order: Vec<String> = decisions(&mut prioritized)
+            .into_iter()
+            .map(|d| d.0)
+            .collect();
+        assert_eq!(order, ["b", "a", "d", "c", "e"]);
+    }
+
+    #[test]
+    fn static_order_fixes_the_decisions() {
+        let text = "-a -b -c -d\n-d -e\n";
+        let order = BranchingHeuristic::StaticOrder(vec![
+            String::from("c"),
+            String::from("e"),
+            String::from("a"),
+        ]);
+        for seed in 0..5 {
+            let mut solver = solver(text);
+            solver.config = solver
+                .config
+                .clone()
+                .branching(order.clone())
+                .seed(Some(seed));
+            // Variables left out of the order come after it, smallest first,
+            // and every decision sets its variable false.
+            let expected = ["c", "e", "a", "b", "d"].map(|v| (String::from(v), false));
+            assert_eq!(decisions(&mut solver), expected);
+        }
+    }
+
+    #[test]
+    fn initial_assignments_seed_the_phases() {
+        // Nothing is implied, so every variable is decided, and only the
+        // seeded phases pick the values.
+        let text = "a b c d\n";
+        let mut initial = HashMap::new();
+        initial.insert(String::from("a"), true);
+        initial.insert(String::from("c"), false);
+        initial.insert(String::from("d"), true);
+        for seed in 0..10 {
+            let mut solver = solver(text);
+            solver.config = solver
+                .config
+                .clone()
+                .branching(BranchingHeuristic::FirstUnassigned)
+                .seed(Some(seed));
+            solver.set_initial_assignment(&initial);
+            let expected = [("a", true), ("b", false), ("c", false), ("d", true)]
+                .map(|(variable, value)| (String::from(variable), value));
+            assert_eq!(decisions(&mut solver), expected);
+        }
+
+        // An infeasible assignment is only a preference, given up on a conflict.
+        let mut solver = solver("-a b\n-a -b\n");
+        solver.config = solver
+            .config
+            .clone()
+            .branching(BranchingHeuristic::FirstUnassigned);
+        let mut initial = HashMap::new();
+        initial.insert(String::from("a"), true);
+        solver.set_initial_assignment(&initial);
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Satisfied);
+        assert_eq!(solver.statistics().conflicts, 1);
+        assert_eq!(solver.literal_value(&literal("a")), Some(false));
+    }
+
+    #[test]
+    fn propagation_matches_the_reference_scan() {
+        let mut rng = StdRng::seed_from_u64(210);
+        for seed in 0..30 {
+            let formula = random_3cnf(&mut rng, 25, 107);
+            for threshold in [None, Some(2), Some(3)] {
+                let config = SolverConfig::default()
+                    .seed(Some(seed))
+                    .verify_propagation(true)
+                    .watch_threshold(threshold)
+                    .learned_first(seed % 2 == 0);
+                let mut solver = CdclSolver::with_config(formula.clone(), config);
+                solver.solve();
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "propagation diverged from the reference scan")]
+    fn verify_propagation_catches_a_missed_unit() {
+        let mut solver = solver("1\n-1 2\n");
+        solver.assignments.assign(String::from("1"), true, None);
+        solver.verify_propagation(HashMap::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "propagation missed a conflict on ¬1 ∨ 2")]
+    fn verify_propagation_catches_a_missed_conflict() {
+        let solver = solver("-1 2\n");
+        let before = [("1", true), ("2", false)]
+            .map(|(variable, value)| (String::from(variable), value))
+            .into_iter()
+            .collect();
+        solver.verify_propagation(before);
+    }
+
+    #[test]
+    fn long_clauses_are_watched_and_short_ones_scanned() {
+        let mut rng = StdRng::seed_from_u64(216);
+        let (mut watched, mut scanned) = (0, 0);
+        for seed in 0..30 {
+            let mut formula = random_3cnf(&mut rng, 20, 70);
+            for _ in 0..20 {
+                let literals = (0..rng.gen_range(6..12))
+                    .map(|_| Literal::new(rng.gen_range(1..=20), rng.gen()))
+                    .collect();
+                formula.clauses.push(Clause::new(literals));
+            }
+            // Too long to scan, but two watches cannot be placed on it.
+            formula
+                .clauses
+                .push(Clause::new(vec![Literal::new(21, false); 5]));
+            formula.variables.insert(21);
+
+            let mut results = Vec::new();
+            for threshold in [None, Some(3), Some(0)] {
+                let config = SolverConfig::default()
+                    .seed(Some(seed))
+                    .verify_propagation(true)
+                    .watch_threshold(threshold);
+                let mut solver = CdclSolver::with_config(formula.clone(), config);
+                assert!(!solver.is_watched(formula.clauses.last().unwrap()));
+                solver.solve();
+                if *solver.sat() == SolverResult::Satisfied {
+                    assert!(solver.verify_model());
+                    assert_eq!(solver.literal_value(&Literal::new(21, false)), Some(true));
+                }
+                let statistics = solver.statistics();
+                match threshold {
+                    None => assert_eq!(statistics.watched_propagations, 0),
+                    Some(3) => {
+                        watched += statistics.watched_propagations;
+                        scanned += statistics.propagations - statistics.watched_propagations;
+                    }
+                    _ => {}
+                }
+                results.push(*solver.sat());
+            }
+            assert!(results.iter().all(|result| *result == results[0]));
+        }
+        assert!(watched > 0);
+        assert!(scanned > 0);
+    }
+
+    #[test]
+    fn learned_units_stay_at_level_zero_across_restarts() {
+        // Deciding `x` first, and true, leads to learning `¬x`.
+        let mut solver = solver("-x a b\n-x a -b\n-x -a b\n-x -a -b\ny z\n-y w\n");
+        let order = BranchingHeuristic::StaticOrder(vec![String::from("x")]);
+        solver.config = solver
+            .config
+            .clone()
+            .branching(order)
+            .restart_base(Some(1))
+            .check_invariants(true);
+        let mut initial = HashMap::new();
+        initial.insert(String::from("x"), true);
+        solver.set_initial_assignment(&initial);
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Satisfied);
+        assert!(solver.statistics().restarts > 0);
+
+        let unit = |solver: &CdclSolver| {
+            assert_eq!(solver.learned_units.len(), 1);
+            let id = solver.learned_units[0];
+            assert_eq!(solver.clause_ref(id).to_string(), "¬x");
+            let assignment = solver.assignments.get(&String::from("x")).unwrap();
+            assert!(!assignment.value);
+            assert_eq!(assignment.dl, 0);
+            assert_eq!(assignment.antecedent, Some(id));
+        };
+        unit(&solver);
+        solver.restart();
+        unit(&solver);
+        // It is set again before the next search propagates anything, and
+        // not learned a second time.
+        solver.reset();
+        unit(&solver);
+        let learned = solver.statistics().learned_clauses;
+        solver.solve();
+        unit(&solver);
+        assert_eq!(solver.statistics().learned_clauses, learned);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn preprocessing_stops_at_its_timeout() {
+        let text = "1 2\n1 2 3\n1 1 4\n-1 -2\n-1 2 -2\n";
+        for (timeout, complete, clauses) in [
+            (Some(Duration::ZERO), false, 4),
+            (Some(Duration::from_secs(60)), true, 3),
+            (None, true, 3),
+        ] {
+            let mut solver = solver(text);
+            solver.config = solver.config.clone().preprocess_timeout(timeout);
+            // Tautologies and repeated literals go even when time is up, as
+            // only the subsumption checks are timed.
+            assert_eq!(solver.preprocess(), complete);
+            assert_eq!(solver.formula.clauses.len(), clauses);
+            assert!(solver.formula.clauses.iter().all(|c| !c.is_tautology()));
+            assert_eq!(solver.verify_clause_db(), Ok(()));
+            solver.solve();
+            assert_eq!(*solver.sat(), SolverResult::Satisfied);
+            assert!(solver.verify_model());
+        }
+    }
+
+    #[test]
+    fn components_solved_separately_agree() {
+        let mut rng = StdRng::seed_from_u64(244);
+        let mut outcomes = HashSet::new();
+        for seed in 0..40 {
+            // Two random halves over variables 1 to 12 and 13 to 24.
+            let mut formula = random_3cnf(&mut rng, 12, 50);
+            let other = random_3cnf(&mut rng, 12, 50);
+            for mut clause in other.clauses {
+                for literal in &mut clause.literals {
+                    literal.value += 12;
+                }
+                formula.clauses.push(clause);
+            }
+            formula.variables = formula
+                .clauses
+                .iter()
+                .flat_map(|c| c.literals.iter().map(|l| l.value))
+                .collect();
+            let components = formula.connected_components();
+            assert!(components.len() >= 2);
+
+            let config = SolverConfig::default().seed(Some(seed));
+            let mut whole = CdclSolver::with_config(formula.clone(), config.clone());
+            whole.solve();
+            let mut split = CdclSolver::with_config(formula, config.decompose(true));
+            split.solve();
+            assert_eq!(split.sat(), whole.sat());
+            if *split.sat() == SolverResult::Satisfied {
+                assert!(split.verify_model());
+                let expected = components.iter().map(|c| c.variables.len()).sum::<usize>();
+                assert_eq!(split.model().unwrap().values.len(), expected);
+            }
+            outcomes.insert(*split.sat() == SolverResult::Satisfied);
+        }
+        assert_eq!(outcomes.len(), 2);
+    }
+
+    // Collects what the solver logs.
+    #[cfg(feature = "std")]
+    struct Log(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "std")]
+    impl Write for Log {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn decomposing_reports_the_components() {
+        let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut formula = crate::parse_text_cnf("a b\n-a c\nd e\n-e\n").unwrap();
+        formula.variables.insert(String::from("f"));
+        let config = SolverConfig::default()
+            .decompose(true)
+            .verbosity(Verbosity::Info);
+        let mut solver = CdclSolver::with_config(formula, config);
+        solver.set_output(Box::new(Log(log.clone())));
+        solver.solve();
+        assert_eq!(*solver.sat(), SolverResult::Satisfied);
+        assert!(solver.verify_model());
+        let log = String::from_utf8(log.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("Solving 3 components separately"), "{}", log);
+    }
+
+    #[test]
+    fn antecedents_survive_deleting_earlier_clauses() {
+        let mut solver = solver("1 2\n3 4\n-1 -2 3\n");
+        let older = solver.add_learned_clause(Clause::new(vec![literal("1"), literal("3")]));
+        let reason = solver.add_learned_clause(Clause::new(vec![literal("2"), literal("4")]));
+        solver.decide(&literal("¬2"));
+        solver.propagate();
+        let four = String::from("4");
+        assert_eq!(
+            solver.assignments.get(&four).unwrap().antecedent,
+            Some(reason)
+        );
+        let position = solver.clause_positions[&reason];
+
+        // Half the learned clauses go, but not a reason: the older one is
+        // removed and the reason moves down a position, keeping its id.
+        solver.reduce_learned(None);
+        assert!(solver.clause(older).is_none());
+        assert_eq!(solver.clause_positions[&reason], position - 1);
+        assert_eq!(
+            solver.assignments.get(&four).unwrap().antecedent,
+            Some(reason)
+        );
+        assert_eq!(solver.clause(reason).unwrap().to_string(), "2 ∨ 4");
+        assert_eq!(solver.verify_clause_db(), Ok(()));
+        solver.check_invariants();
+    }
+
+    #[test]
+    fn results_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(249);
+        let mut outcomes = HashSet::new();
+        for seed in 0..200 {
+            let formula = random_3cnf(&mut rng, 8, 34);
+            let expected = crate::reference::brute_force_sat(&formula);
+            let config = SolverConfig::default()
+                .seed(Some(seed))
+                .reduce_interval(Some(2))
+                .check_invariants(true);
+            let mut solver = CdclSolver::with_config(formula, config);
+            solver.solve();
+            assert_eq!(*solver.sat(), expected);
+            outcomes.insert(expected == SolverResult::Satisfied);
+        }
+        assert_eq!(outcomes.len(), 2);
+    }
+
+    #[test]
+    fn learned_clauses_are_checked_against_the_formula() {
+        let mut narrow = solver("1 2\n-1 2\n3 4\n");
+        let clause = |literals: &[&str]| Clause::new(literals.iter().map(|l| literal(l)).collect());
+        assert_eq!(narrow.check_learned_clause(&clause(&["2"])), Some(true));
+        assert_eq!(
+            narrow.check_learned_clause(&clause(&["2", "5"])),
+            Some(true)
+        );
+        assert_eq!(narrow.check_learned_clause(&clause(&["1"])), Some(false));
+        assert_eq!(narrow.check_learned_clause(&clause(&["3"])), Some(false));
+
+        let wide: String = (1..=TEACHING_MAX_VARIABLES + 1)
+            .map(|v| format!("{}\n", v))
+            .collect();
+        let mut wide = solver(&wide);
+        assert_eq!(wide.check_learned_clause(&clause(&["1"])), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn teaching_finds_every_learned_clause_entailed() {
+        let mut rng = StdRng::seed_from_u64(250);
+        let mut checked = 0;
+        for seed in 0..30 {
+            let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let config = SolverConfig::default()
+                .seed(Some(seed))
+                .teaching(true)
+                .verbosity(Verbosity::Info);
+            let mut solver = CdclSolver::with_config(random_3cnf(&mut rng, 10, 43), config);
+            solver.set_output(Box::new(Log(log.clone())));
+            solver.solve();
+            let log = String::from_utf8(log.lock().unwrap().clone()).unwrap();
+            assert!(!log.contains("NOT entailed"), "{}", log);
+            let lines = log.lines().filter(|line| line.starts_with("Checked: "));
+            checked += lines.count();
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn free_variables_follow_the_policy() {
+        // `1` is forced and the search stops there, leaving `2` and the
+        // declared `3` free.
+        let text = "p cnf 3 2\n1 0\n1 2 0\n";
+        for policy in [
+            FreePolicy::AllTrue,
+            FreePolicy::AllFalse,
+            FreePolicy::Omit,
+            FreePolicy::Mark,
+        ] {
+            let config = SolverConfig::default().free_variable_policy(policy);
+            let mut solver =
+                CdclSolver::with_config(crate::parse_dimacs_cnf(text).unwrap(), config);
+            solver.set_output(sink());
+            assert!(solver.is_satisfiable());
+            let model = solver.model().unwrap();
+            assert_eq!(model.values.get(&1), Some(&true));
+            let free = match policy {
+                FreePolicy::AllTrue => Some(true),
+                FreePolicy::AllFalse => Some(false),
+                FreePolicy::Omit | FreePolicy::Mark => None,
+            };
+            assert_eq!(model.values.get(&2).copied(), free);
+            assert_eq!(model.values.get(&3).copied(), free);
+            assert_eq!(solver.value_of(&2), free);
+            let marked = if policy == FreePolicy::Mark { 2 } else { 0 };
+            assert_eq!(model.free.len(), marked);
+        }
+    }
+}
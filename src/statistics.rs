@@ -0,0 +1,90 @@
+use core::fmt;
+use core::time::Duration;
+
+#[derive(Clone, Default, Debug)]
+pub struct Statistics {
+    pub decisions: u64,
+    pub propagations: u64,
+    pub watched_propagations: u64,
+    pub conflicts: u64,
+    pub learned_clauses: u64,
+    pub restarts: u64,
+    pub max_decision_level: i32,
+    pub pure_literals: u64,
+    pub deleted_clauses: u64,
+    // Learned clauses not added again as an equal clause was present.
+    pub duplicate_learned_clauses: u64,
+    // Clauses looked at by the propagation scan, and the units it found in
+    // learned clauses.
+    pub scanned_clauses: u64,
+    pub learned_propagations: u64,
+    // Only counted while a proof is logged: the lemmas written to it, and the
+    // clauses they were derived from, summed over all lemmas.
+    pub proof_lemmas: u64,
+    pub proof_hints: u64,
+}
+
+impl Statistics {
+    pub fn add(&mut self, other: &Statistics) {
+        self.decisions += other.decisions;
+        self.propagations += other.propagations;
+        self.watched_propagations += other.watched_propagations;
+        self.conflicts += other.conflicts;
+        self.learned_clauses += other.learned_clauses;
+        self.restarts += other.restarts;
+        self.max_decision_level = self.max_decision_level.max(other.max_decision_level);
+        self.pure_literals += other.pure_literals;
+        self.deleted_clauses += other.deleted_clauses;
+        self.duplicate_learned_clauses += other.duplicate_learned_clauses;
+        self.scanned_clauses += other.scanned_clauses;
+        self.learned_propagations += other.learned_propagations;
+        self.proof_lemmas += other.proof_lemmas;
+        self.proof_hints += other.proof_hints;
+    }
+
+    // Conflicts and propagations per second over `elapsed`, both zero when no
+    // time was measured.
+    pub fn rates(&self, elapsed: Duration) -> (f64, f64) {
+        let seconds = elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return (0.0, 0.0);
+        }
+        (
+            self.conflicts as f64 / seconds,
+            self.propagations as f64 / seconds,
+        )
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Decisions: {}", self.decisions)?;
+        writeln!(f, "Propagations: {}", self.propagations)?;
+        writeln!(f, "Conflicts: {}", self.conflicts)?;
+        writeln!(f, "Learned clauses: {}", self.learned_clauses)?;
+        writeln!(f, "Restarts: {}", self.restarts)?;
+        writeln!(f, "Max decision level: {}", self.max_decision_level)?;
+        write!(f, "Pure literals: {}", self.pure_literals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_are_per_second() {
+        let statistics = Statistics {
+            conflicts: 30,
+            propagations: 1200,
+            ..Statistics::default()
+        };
+        assert_eq!(statistics.rates(Duration::from_secs(2)), (15.0, 600.0));
+        assert_eq!(statistics.rates(Duration::from_millis(500)), (60.0, 2400.0));
+        assert_eq!(statistics.rates(Duration::ZERO), (0.0, 0.0));
+        assert_eq!(
+            Statistics::default().rates(Duration::from_secs(1)),
+            (0.0, 0.0)
+        );
+    }
+}
@@ -1,17 +1,33 @@
-use crate::wff::{Clause, Formula, Literal};
-use std::collections::HashMap;
+use crate::collections::HashMap;
+use crate::error::Error;
+use crate::parser::parse_expr;
+use crate::wff::{Clause, Expr, Formula, Literal};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct EncodeStats {
+    pub auxiliary_variables: usize,
+    pub clauses: usize,
+    // Subexpressions that got an existing variable from `variable_map`.
+    pub reused_subexpressions: usize,
+}
+
+#[derive(Default)]
 pub struct TseitinEncoder {
     variable_counter: usize,
-    variable_map: HashMap<String, String>,
+    // Gate variables by the subexpression they stand for, within the
+    // current encoding. Gate variables are numbered across encodings, so
+    // those of separate ones never clash.
+    variable_map: HashMap<Expr, String>,
+    reuse_counter: usize,
 }
 
 impl TseitinEncoder {
     pub fn new() -> Self {
-        TseitinEncoder {
-            variable_counter: 0,
-            variable_map: HashMap::new(),
-        }
+        TseitinEncoder::default()
     }
 
     fn new_variable(&mut self) -> String {
@@ -19,81 +35,229 @@ impl TseitinEncoder {
         format!("t_{}", self.variable_counter)
     }
 
-    pub fn encode(&mut self, formula: &Formula) -> Formula {
+    // Parses `s` as `parse_expr` does and encodes it.
+    pub fn encode(&mut self, s: &str) -> Result<Formula, Error> {
+        Ok(self.encode_expr(&parse_expr(s)?))
+    }
+
+    // Every gate variable of the result is defined by its own clauses, as
+    // subexpressions are only shared within one encoding.
+    pub fn encode_expr(&mut self, expr: &Expr) -> Formula {
+        self.variable_map.clear();
         let mut clauses = Vec::new();
-        let root_var = self.encode_recursive(&formula.to_string(), &mut clauses);
+        let root = self.encode_recursive(expr, &mut clauses);
 
-        // Add the root variable as a unit clause
-        clauses.push(Clause::new(vec![Literal::new(root_var, false)]));
+        // Add the root literal as a unit clause
+        clauses.push(Clause::new(vec![root]));
 
         Formula::new(clauses)
     }
 
-    fn encode_recursive(&mut self, subformula: &str, clauses: &mut Vec<Clause>) -> String {
-        if let Some(var) = self.variable_map.get(subformula) {
-            return var.clone();
+    pub fn encode_with_stats(&mut self, expr: &Expr) -> (Formula, EncodeStats) {
+        let variables_before = self.variable_counter;
+        let reused_before = self.reuse_counter;
+        let formula = self.encode_expr(expr);
+        let stats = EncodeStats {
+            auxiliary_variables: self.variable_counter - variables_before,
+            clauses: formula.clauses.len(),
+            reused_subexpressions: self.reuse_counter - reused_before,
+        };
+        (formula, stats)
+    }
+
+    fn encode_recursive(&mut self, expr: &Expr, clauses: &mut Vec<Clause>) -> Literal {
+        let (left, right) = match expr {
+            // Base case: input variables and their negations need no gate
+            Expr::Var(name) => return Literal::new(name.clone(), false),
+            Expr::Not(e) => return self.encode_recursive(e, clauses).negate(),
+            Expr::And(a, b)
+            | Expr::Or(a, b)
+            | Expr::Implies(a, b)
+            | Expr::Iff(a, b)
+            | Expr::Xor(a, b) => (a, b),
+        };
+
+        if let Some(var) = self.variable_map.get(expr) {
+            self.reuse_counter += 1;
+            return Literal::new(var.clone(), false);
         }
 
+        let a = self.encode_recursive(left, clauses);
+        let b = self.encode_recursive(right, clauses);
         let var = self.new_variable();
-        self.variable_map
-            .insert(subformula.to_string(), var.clone());
-
-        if !subformula.contains('∧') && !subformula.contains('∨') {
-            // Base case: literal
-            let negated = subformula.starts_with('¬');
-            let literal = if negated {
-                Literal::new(subformula[1..].to_string(), false)
-            } else {
-                Literal::new(subformula.to_string(), false)
-            };
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), true),
-                literal.clone(),
-            ]));
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), false),
-                literal.negate(),
-            ]));
-        } else if subformula.contains('∧') {
-            // AND operation
-            let parts: Vec<&str> = subformula.split('∧').map(|s| s.trim()).collect();
-            let left_var = self.encode_recursive(parts[0], clauses);
-            let right_var = self.encode_recursive(parts[1], clauses);
-
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), true),
-                Literal::new(left_var.clone(), false),
-            ]));
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), true),
-                Literal::new(right_var.clone(), false),
-            ]));
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), false),
-                Literal::new(left_var, true),
-                Literal::new(right_var, true),
-            ]));
-        } else if subformula.contains('∨') {
-            // OR operation
-            let parts: Vec<&str> = subformula.split('∨').map(|s| s.trim()).collect();
-            let left_var = self.encode_recursive(parts[0], clauses);
-            let right_var = self.encode_recursive(parts[1], clauses);
-
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), false),
-                Literal::new(left_var.clone(), true),
-            ]));
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), false),
-                Literal::new(right_var.clone(), true),
-            ]));
-            clauses.push(Clause::new(vec![
-                Literal::new(var.clone(), true),
-                Literal::new(left_var, false),
-                Literal::new(right_var, false),
-            ]));
+        self.variable_map.insert(expr.clone(), var.clone());
+        let t = Literal::new(var, false);
+
+        let gate: Vec<Vec<Literal>> = match expr {
+            // t ↔ (a ∧ b)
+            Expr::And(..) => vec![
+                vec![t.negate(), a.clone()],
+                vec![t.negate(), b.clone()],
+                vec![t.clone(), a.negate(), b.negate()],
+            ],
+            // t ↔ (a ∨ b)
+            Expr::Or(..) => vec![
+                vec![t.clone(), a.negate()],
+                vec![t.clone(), b.negate()],
+                vec![t.negate(), a.clone(), b.clone()],
+            ],
+            // t ↔ (¬a ∨ b)
+            Expr::Implies(..) => vec![
+                vec![t.clone(), a.clone()],
+                vec![t.clone(), b.negate()],
+                vec![t.negate(), a.negate(), b.clone()],
+            ],
+            // t ↔ (a ↔ b)
+            Expr::Iff(..) => vec![
+                vec![t.negate(), a.negate(), b.clone()],
+                vec![t.negate(), a.clone(), b.negate()],
+                vec![t.clone(), a.clone(), b.clone()],
+                vec![t.clone(), a.negate(), b.negate()],
+            ],
+            // t ↔ (a ⊕ b)
+            Expr::Xor(..) => vec![
+                vec![t.negate(), a.clone(), b.clone()],
+                vec![t.negate(), a.negate(), b.negate()],
+                vec![t.clone(), a.negate(), b.clone()],
+                vec![t.clone(), a.clone(), b.negate()],
+            ],
+            Expr::Var(_) | Expr::Not(_) => unreachable!(),
+        };
+        for literals in gate {
+            clauses.push(Clause::new(literals));
+        }
+
+        t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counting::count_models;
+    use crate::reference::brute_force_sat;
+    use crate::solver::SolverResult;
+    use alloc::string::ToString;
+
+    #[test]
+    fn encodes_a_parsed_formula() {
+        let mut encoder = TseitinEncoder::new();
+        let formula = encoder.encode("(a ∧ b) ∨ ¬(a ∧ b)").unwrap();
+        // Every assignment of a and b extends to exactly one model.
+        assert_eq!(count_models(&formula), Some(4));
+    }
+
+    #[test]
+    fn stats_count_shared_subexpressions() {
+        let stats = |text: &str| {
+            let expr = parse_expr(text).unwrap();
+            TseitinEncoder::new().encode_with_stats(&expr).1
+        };
+        let shared = stats("(a ∧ b) ∨ ¬(a ∧ b)");
+        let distinct = stats("(a ∧ b) ∨ ¬(c ∧ d)");
+        assert_eq!(shared.reused_subexpressions, 1);
+        assert_eq!(distinct.reused_subexpressions, 0);
+        assert!(shared.auxiliary_variables < distinct.auxiliary_variables);
+        assert!(shared.clauses < distinct.clauses);
+    }
+
+    #[test]
+    fn xor_of_three_has_four_models() {
+        let expr = parse_expr("a ⊕ b ⊕ c").unwrap();
+        let formula = TseitinEncoder::new().encode_expr(&expr);
+        assert_eq!(count_models(&formula), Some(4));
+    }
+
+    #[test]
+    fn later_encodings_define_their_gates() {
+        let mut encoder = TseitinEncoder::new();
+        let expr = parse_expr("a ∧ b").unwrap();
+        let first = encoder.encode_expr(&expr);
+        let second = encoder.encode_expr(&expr);
+        assert_eq!(first.clauses.len(), second.clauses.len());
+        let gates = |formula: &Formula| {
+            let mut gates: Vec<String> = formula
+                .variables
+                .iter()
+                .filter(|variable| variable.starts_with("t_"))
+                .cloned()
+                .collect();
+            gates.sort();
+            gates
+        };
+        assert_eq!(gates(&first), ["t_1"]);
+        assert_eq!(gates(&second), ["t_2"]);
+        assert_eq!(count_models(&second), Some(1));
+    }
+
+    #[test]
+    fn xor_gates_take_four_clauses_and_are_shared() {
+        let expr = parse_expr("a ⊕ b").unwrap();
+        let (formula, stats) = TseitinEncoder::new().encode_with_stats(&expr);
+        // The four gate clauses and the unit asserting the root.
+        assert_eq!(stats.clauses, 5);
+        assert_eq!(count_models(&formula), Some(2));
+        let expr = parse_expr("(a ⊕ b) ∧ ¬(a ⊕ b)").unwrap();
+        let (formula, stats) = TseitinEncoder::new().encode_with_stats(&expr);
+        assert_eq!(stats.reused_subexpressions, 1);
+        assert_eq!(count_models(&formula), Some(0));
+    }
+
+    #[test]
+    fn nested_operands_share_their_variables() {
+        let expr = parse_expr("(a ∨ b) ∧ (c ∨ ¬a)").unwrap();
+        let formula = TseitinEncoder::new().encode_expr(&expr);
+        let mut variables: Vec<&String> = formula.variables.iter().collect();
+        variables.sort();
+        // One a for both of its occurrences, and a gate per binary operator.
+        assert_eq!(variables, ["a", "b", "c", "t_1", "t_2", "t_3"]);
+        // Three clauses for each gate and the unit asserting the root.
+        assert_eq!(formula.clauses.len(), 10);
+        assert_eq!(count_models(&formula), Some(4));
+    }
+
+    #[test]
+    fn implications_and_equivalences_follow_their_truth_tables() {
+        fn value(expr: &Expr, values: &HashMap<&str, bool>) -> bool {
+            match expr {
+                Expr::Var(name) => values[name.as_str()],
+                Expr::Not(e) => !value(e, values),
+                Expr::And(a, b) => value(a, values) && value(b, values),
+                Expr::Or(a, b) => value(a, values) || value(b, values),
+                Expr::Implies(a, b) => !value(a, values) || value(b, values),
+                Expr::Iff(a, b) => value(a, values) == value(b, values),
+                Expr::Xor(a, b) => value(a, values) != value(b, values),
+            }
         }
 
-        var
+        for text in ["a → b", "a ↔ b", "¬(a → b)", "(a → b) ↔ (b → c)", "a ↔ ¬a"] {
+            let expr = parse_expr(text).unwrap();
+            let encoding = TseitinEncoder::new().encode_expr(&expr);
+            for bits in 0..8 {
+                let values: HashMap<&str, bool> = ["a", "b", "c"]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| (*name, bits >> i & 1 == 1))
+                    .collect();
+                // The encoding with the inputs fixed is SAT exactly when the
+                // formula holds under them.
+                let mut clauses = encoding.clauses.clone();
+                for (name, value) in &values {
+                    clauses.push(Clause::new(vec![Literal::new(name.to_string(), !value)]));
+                }
+                let expected = if value(&expr, &values) {
+                    SolverResult::Satisfied
+                } else {
+                    SolverResult::Unresolved
+                };
+                assert_eq!(
+                    brute_force_sat(&Formula::new(clauses)),
+                    expected,
+                    "{} under {:?}",
+                    text,
+                    values
+                );
+            }
+        }
     }
 }
@@ -1,62 +1,160 @@
-use std::collections::HashSet;
+use crate::collections::HashMap;
+use crate::collections::HashSet;
+use crate::gates;
+use crate::gates::Gate;
+use crate::preprocess::Preprocessor;
+use crate::preprocess::TautologyRemoval;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::str::FromStr;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Literal {
-    pub value: String,
+// Orders variable names numerically when they are numbers, so DIMACS
+// variables sort as 1, 2, 10 rather than 1, 10, 2.
+pub fn variable_key(name: &str) -> (u64, &str) {
+    (name.parse::<u64>().unwrap_or(u64::MAX), name)
+}
+
+// What formulas and the solver need of a variable. Named variables are
+// `String`s; DIMACS ones can stay `u32`s, which hash and compare cheaper.
+pub trait Variable: Clone + Eq + Hash + fmt::Display + fmt::Debug + Send + Sync {
+    // The variable's own number, if it is one.
+    fn number(&self) -> Option<u64>;
+
+    // Orders variables as `variable_key` orders their names.
+    fn key(&self) -> (u64, &str);
+}
+
+impl Variable for String {
+    fn number(&self) -> Option<u64> {
+        self.parse().ok()
+    }
+
+    fn key(&self) -> (u64, &str) {
+        variable_key(self)
+    }
+}
+
+impl Variable for u32 {
+    fn number(&self) -> Option<u64> {
+        Some(u64::from(*self))
+    }
+
+    fn key(&self) -> (u64, &str) {
+        (u64::from(*self), "")
+    }
+}
+
+// A variable or its negation. Variables are named by default, as in
+// `StrLiteral`; DIMACS input can use its numbers directly as `Literal<u32>`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Literal<V = String> {
+    pub value: V,
     pub negation: bool,
 }
 
-impl Literal {
-    pub fn new(value: String, negation: bool) -> Literal {
+// Literals over named variables, such as the `t_1` ones Tseitin encodings add.
+pub type StrLiteral = Literal<String>;
+
+impl<V: Eq + Hash + Clone> Literal<V> {
+    pub fn new(value: V, negation: bool) -> Literal<V> {
         Literal { value, negation }
     }
 
-    pub fn negate(&self) -> Literal {
-        return Literal {
+    pub fn negate(&self) -> Literal<V> {
+        Literal {
             value: self.value.clone(),
             negation: !self.negation,
-        };
+        }
     }
+}
 
-    pub fn to_string(&self) -> String {
+impl<V: fmt::Display> fmt::Display for Literal<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.negation {
-            return format!("¬{}", self.value);
+            write!(f, "¬{}", self.value)
         } else {
-            return self.value.clone();
+            write!(f, "{}", self.value)
         }
     }
 }
 
-#[derive(Clone)]
-pub struct Clause {
-    pub literals: Vec<Literal>,
+// Reads what `Display` writes. A leading `¬` always negates, so a variable
+// whose own name starts with one does not read back unnegated.
+impl<V: FromStr> FromStr for Literal<V> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Literal<V>, String> {
+        let (name, negation) = match s.strip_prefix('¬') {
+            Some(name) => (name, true),
+            None => (s, false),
+        };
+        match name.parse() {
+            Ok(value) => Ok(Literal { value, negation }),
+            Err(_) => Err(format!("invalid literal '{}'", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Clause<V = String> {
+    pub literals: Vec<Literal<V>>,
 }
 
-impl Clause {
-    pub fn new(literals: Vec<Literal>) -> Clause {
+impl<V: Eq + Hash + Clone> Clause<V> {
+    pub fn new(literals: Vec<Literal<V>>) -> Clause<V> {
         Clause { literals }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut result = String::new();
+    // Whether the clause holds some literal and its negation, and so is true
+    // under every assignment.
+    pub fn is_tautology(&self) -> bool {
+        self.literals
+            .iter()
+            .any(|literal| self.literals.contains(&literal.negate()))
+    }
+}
+
+// The empty clause, which nothing satisfies, is written `⊥`.
+impl<V: fmt::Display> fmt::Display for Clause<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.literals.is_empty() {
+            return write!(f, "⊥");
+        }
         for (i, literal) in self.literals.iter().enumerate() {
-            result.push_str(&literal.to_string());
+            write!(f, "{}", literal)?;
             if i < self.literals.len() - 1 {
-                result.push_str(" ∨ ");
+                write!(f, " ∨ ")?;
             }
         }
-        return result;
+        Ok(())
+    }
+}
+
+impl<V: Variable> Clause<V> {
+    // The distinct literals in variable order, the same for clauses that
+    // differ only in order and repetition.
+    pub fn canonical(&self) -> Vec<Literal<V>> {
+        let mut literals = self.literals.clone();
+        literals.sort_by(|a, b| (a.value.key(), a.negation).cmp(&(b.value.key(), b.negation)));
+        literals.dedup();
+        literals
     }
 }
 
 #[derive(Clone)]
-pub struct Formula {
-    pub clauses: Vec<Clause>,
-    pub variables: HashSet<String>,
+pub struct Formula<V = String> {
+    pub clauses: Vec<Clause<V>>,
+    pub variables: HashSet<V>,
 }
 
-impl Formula {
-    pub fn new(clauses: Vec<Clause>) -> Formula {
+impl<V: Variable> Formula<V> {
+    pub fn new(clauses: Vec<Clause<V>>) -> Formula<V> {
         let mut variables = HashSet::new();
         for clause in &clauses {
             for literal in &clause.literals {
@@ -66,14 +164,539 @@ impl Formula {
         Formula { clauses, variables }
     }
 
-    pub fn to_string(&self) -> String {
+    // `new` without repeated literals and without tautologies, clauses holding
+    // a literal and its negation, which are dropped. Returns how many were.
+    // Variables only the dropped clauses mentioned stay in the formula.
+    pub fn simplified(clauses: Vec<Clause<V>>) -> (Formula<V>, usize) {
+        let mut formula = Formula::new(clauses);
+        let before = formula.clauses.len();
+        TautologyRemoval.apply(&mut formula);
+        let removed = before - formula.clauses.len();
+        (formula, removed)
+    }
+
+    // Every literal occurrence, clause by clause.
+    pub fn literals(&self) -> impl Iterator<Item = &Literal<V>> {
+        self.clauses
+            .iter()
+            .flat_map(|clause| clause.literals.iter())
+    }
+
+    // Maps each variable to its (positive, negative) occurrence counts.
+    pub fn polarity_summary(&self) -> HashMap<V, (usize, usize)> {
+        let mut summary: HashMap<V, (usize, usize)> = HashMap::new();
+        for literal in self.literals() {
+            let counts = summary.entry(literal.value.clone()).or_insert((0, 0));
+            if literal.negation {
+                counts.1 += 1;
+            } else {
+                counts.0 += 1;
+            }
+        }
+        summary
+    }
+
+    // Counts, for each pair of variables, the clauses they occur in together.
+    // Each pair is keyed once, its smaller variable first.
+    pub fn variable_interaction_graph(&self) -> HashMap<(V, V), usize> {
+        let mut graph: HashMap<(V, V), usize> = HashMap::new();
+        for clause in &self.clauses {
+            let mut variables: Vec<&V> = clause.literals.iter().map(|l| &l.value).collect();
+            variables.sort_by_key(|v| v.key());
+            variables.dedup();
+            for (i, a) in variables.iter().enumerate() {
+                for b in &variables[i + 1..] {
+                    *graph.entry(((*a).clone(), (*b).clone())).or_insert(0) += 1;
+                }
+            }
+        }
+        graph
+    }
+
+    fn sorted_interaction_edges(&self) -> Vec<((V, V), usize)> {
+        let mut edges: Vec<((V, V), usize)> =
+            self.variable_interaction_graph().into_iter().collect();
+        edges.sort_by(|((a1, b1), _), ((a2, b2), _)| {
+            (a1.key(), b1.key()).cmp(&(a2.key(), b2.key()))
+        });
+        edges
+    }
+
+    // One "a b weight" line per edge.
+    pub fn interaction_graph_edge_list(&self) -> String {
         let mut result = String::new();
+        for ((a, b), weight) in self.sorted_interaction_edges() {
+            result.push_str(&format!("{} {} {}\n", a, b, weight));
+        }
+        result
+    }
+
+    pub fn interaction_graph_dot(&self) -> String {
+        let mut result = String::from("graph interaction {\n");
+        for ((a, b), weight) in self.sorted_interaction_edges() {
+            result.push_str(&format!(
+                "    \"{}\" -- \"{}\" [weight={}];\n",
+                a, b, weight
+            ));
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    // Splits the formula into sub-formulas sharing no variables, the
+    // components of the variable interaction graph found by union-find.
+    // Variables no clause mentions form components of their own without
+    // clauses, and so does every empty clause. Components are ordered by
+    // their smallest variable, empty clauses last.
+    pub fn connected_components(&self) -> Vec<Formula<V>> {
+        let mut variables: Vec<&V> = self.variables.iter().collect();
+        variables.sort_by_key(|v| v.key());
+        let index: HashMap<&V, usize> =
+            variables.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let mut parent: Vec<usize> = (0..variables.len()).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        // Joining each variable to the first of its clause connects the same
+        // variables as the clause's edges in the graph.
+        for clause in &self.clauses {
+            if let Some(first) = clause.literals.first() {
+                let mut a = find(&mut parent, index[&first.value]);
+                for literal in &clause.literals[1..] {
+                    let b = find(&mut parent, index[&literal.value]);
+                    parent[a.max(b)] = a.min(b);
+                    a = a.min(b);
+                }
+            }
+        }
+
+        let mut components: Vec<Formula<V>> = Vec::new();
+        let mut component_of: HashMap<usize, usize> = HashMap::new();
+        for (i, variable) in variables.iter().enumerate() {
+            let root = find(&mut parent, i);
+            let component = *component_of.entry(root).or_insert_with(|| {
+                components.push(Formula {
+                    clauses: Vec::new(),
+                    variables: HashSet::new(),
+                });
+                components.len() - 1
+            });
+            components[component].variables.insert((*variable).clone());
+        }
+        let mut empty: Vec<Formula<V>> = Vec::new();
+        for clause in &self.clauses {
+            match clause.literals.first() {
+                Some(first) => {
+                    let root = find(&mut parent, index[&first.value]);
+                    components[component_of[&root]].clauses.push(clause.clone());
+                }
+                None => empty.push(Formula::new(vec![clause.clone()])),
+            }
+        }
+        components.extend(empty);
+        components
+    }
+
+    // Eliminates variables from the interaction graph one at a time, always
+    // one with the fewest neighbours left (the smallest on ties), and joins the
+    // neighbours of each into a clique.
+    pub fn min_degree_elimination_order(&self) -> Vec<V> {
+        self.min_degree_elimination().0
+    }
+
+    // The largest neighbourhood met along `min_degree_elimination_order`. This
+    // is the width of one tree decomposition, so an upper bound on the
+    // treewidth rather than its exact value.
+    pub fn estimated_treewidth(&self) -> usize {
+        self.min_degree_elimination().1
+    }
+
+    fn min_degree_elimination(&self) -> (Vec<V>, usize) {
+        let mut neighbours: HashMap<&V, HashSet<&V>> = self
+            .variables
+            .iter()
+            .map(|variable| (variable, HashSet::new()))
+            .collect();
+        for clause in &self.clauses {
+            for a in &clause.literals {
+                for b in &clause.literals {
+                    if a.value != b.value {
+                        neighbours.get_mut(&a.value).unwrap().insert(&b.value);
+                    }
+                }
+            }
+        }
+
+        let mut order: Vec<V> = Vec::new();
+        let mut width = 0;
+        while let Some(variable) = neighbours
+            .iter()
+            .min_by(|(a, a_neighbours), (b, b_neighbours)| {
+                (a_neighbours.len(), a.key()).cmp(&(b_neighbours.len(), b.key()))
+            })
+            .map(|(variable, _)| *variable)
+        {
+            let adjacent = neighbours.remove(variable).unwrap();
+            width = width.max(adjacent.len());
+            for a in &adjacent {
+                let entry = neighbours.get_mut(a).unwrap();
+                entry.remove(variable);
+                entry.extend(adjacent.iter().filter(|b| *b != a));
+            }
+            order.push(variable.clone());
+        }
+        (order, width)
+    }
+
+    pub fn simplify_tautologies_and_units(&self) -> Option<(Formula<V>, Vec<Literal<V>>)> {
+        let mut clauses: Vec<Vec<Literal<V>>> = Vec::new();
+        for clause in &self.clauses {
+            let mut literals: Vec<Literal<V>> = Vec::new();
+            for literal in &clause.literals {
+                if !literals.contains(literal) {
+                    literals.push(literal.clone());
+                }
+            }
+            if literals.is_empty() {
+                return None;
+            }
+            if literals.iter().any(|l| literals.contains(&l.negate())) {
+                continue;
+            }
+            clauses.push(literals);
+        }
+
+        let mut forced: Vec<Literal<V>> = Vec::new();
+        while let Some(unit) = clauses.iter().find(|c| c.len() == 1).map(|c| c[0].clone()) {
+            let falsified = unit.negate();
+            let mut remaining: Vec<Vec<Literal<V>>> = Vec::new();
+            for mut literals in clauses {
+                if literals.contains(&unit) {
+                    continue;
+                }
+                literals.retain(|l| *l != falsified);
+                if literals.is_empty() {
+                    return None;
+                }
+                remaining.push(literals);
+            }
+            clauses = remaining;
+            forced.push(unit);
+        }
+
+        let clauses = clauses.into_iter().map(Clause::new).collect();
+        Some((Formula::new(clauses), forced))
+    }
+
+    pub fn remove_backbone_satisfied(&mut self, backbone: &[Literal<V>]) {
+        let falsified: Vec<Literal<V>> = backbone.iter().map(|l| l.negate()).collect();
+        self.clauses
+            .retain(|clause| !clause.literals.iter().any(|l| backbone.contains(l)));
+        for clause in &mut self.clauses {
+            clause.literals.retain(|l| !falsified.contains(l));
+        }
+        // The variables stay declared, even those no clause mentions now.
+    }
+}
+
+impl Formula {
+    pub fn extract_gates(&self) -> Vec<Gate> {
+        gates::extract_gates(self)
+    }
+
+    // Adds `clauses` guarded by a fresh selector variable. The group is only
+    // enforced when the returned literal is assumed true.
+    pub fn add_clause_group(&mut self, clauses: Vec<Clause>) -> Literal {
+        let mut index = 1;
+        while self.variables.contains(&format!("s_{}", index)) {
+            index += 1;
+        }
+        let selector = Literal::new(format!("s_{}", index), false);
+        self.variables.insert(selector.value.clone());
+
+        for mut clause in clauses {
+            for literal in &clause.literals {
+                self.variables.insert(literal.value.clone());
+            }
+            clause.literals.push(selector.negate());
+            self.clauses.push(clause);
+        }
+        selector
+    }
+}
+
+// The formula without clauses, which everything satisfies, is written `⊤`.
+impl<V: fmt::Display> fmt::Display for Formula<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.clauses.is_empty() {
+            return write!(f, "⊤");
+        }
         for (i, clause) in self.clauses.iter().enumerate() {
-            result.push_str(&clause.to_string());
+            write!(f, "{}", clause)?;
             if i < self.clauses.len() - 1 {
-                result.push_str(" ∧ ");
+                write!(f, " ∧ ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Partial MaxSAT input: every hard clause must hold, and the weights of the
+// soft clauses left unsatisfied are minimized.
+#[derive(Clone)]
+pub struct WeightedFormula {
+    pub hard: Vec<Clause>,
+    pub soft: Vec<(u64, Clause)>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Expr {
+    Var(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Implies(Box<Expr>, Box<Expr>),
+    Iff(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn var(name: &str) -> Expr {
+        Expr::Var(name.to_string())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(e: Expr) -> Expr {
+        Expr::Not(Box::new(e))
+    }
+
+    pub fn and(a: Expr, b: Expr) -> Expr {
+        Expr::And(Box::new(a), Box::new(b))
+    }
+
+    pub fn or(a: Expr, b: Expr) -> Expr {
+        Expr::Or(Box::new(a), Box::new(b))
+    }
+
+    pub fn implies(a: Expr, b: Expr) -> Expr {
+        Expr::Implies(Box::new(a), Box::new(b))
+    }
+
+    pub fn iff(a: Expr, b: Expr) -> Expr {
+        Expr::Iff(Box::new(a), Box::new(b))
+    }
+
+    pub fn xor(a: Expr, b: Expr) -> Expr {
+        Expr::Xor(Box::new(a), Box::new(b))
+    }
+
+    fn is_binary(&self) -> bool {
+        !matches!(self, Expr::Var(_) | Expr::Not(_))
+    }
+}
+
+// Compound operands are always parenthesized, so the output never depends on
+// operator precedence.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, op, b) = match self {
+            Expr::Var(name) => return write!(f, "{}", name),
+            Expr::Not(e) => {
+                return if e.is_binary() {
+                    write!(f, "¬({})", e)
+                } else {
+                    write!(f, "¬{}", e)
+                };
+            }
+            Expr::And(a, b) => (a, "∧", b),
+            Expr::Or(a, b) => (a, "∨", b),
+            Expr::Implies(a, b) => (a, "→", b),
+            Expr::Iff(a, b) => (a, "↔", b),
+            Expr::Xor(a, b) => (a, "⊕", b),
+        };
+        if a.is_binary() {
+            write!(f, "({})", a)?;
+        } else {
+            write!(f, "{}", a)?;
+        }
+        write!(f, " {} ", op)?;
+        if b.is_binary() {
+            write!(f, "({})", b)
+        } else {
+            write!(f, "{}", b)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_text_cnf;
+
+    #[test]
+    fn backbone_satisfied_clauses_are_removed() {
+        let mut formula = parse_text_cnf("a b\n-a c\nb d\n-a\n").unwrap();
+        formula.remove_backbone_satisfied(&["¬a".parse().unwrap()]);
+        let clauses: Vec<String> = formula.clauses.iter().map(|c| c.to_string()).collect();
+        let expected: Vec<String> = parse_text_cnf("b\nb d\n")
+            .unwrap()
+            .clauses
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(clauses, expected);
+        // a no longer occurs, yet it remains a variable of the formula.
+        assert!(formula.variables.contains("a"));
+        assert_eq!(formula.variables.len(), 4);
+    }
+
+    #[test]
+    fn simplify_cleans_a_messy_formula() {
+        // A tautology, a unit, a clause the unit satisfies and one it
+        // shortens, with a repeated literal.
+        let formula = parse_text_cnf("a -a b\nc\nc e\n-c d e e\n").unwrap();
+        let (cleaned, forced) = formula.simplify_tautologies_and_units().unwrap();
+        let clauses: Vec<String> = cleaned.clauses.iter().map(|c| c.to_string()).collect();
+        assert_eq!(clauses, ["d ∨ e"]);
+        assert_eq!(forced, ["c".parse::<Literal>().unwrap()]);
+        let unsat = parse_text_cnf("a\n-a b\n-b\n").unwrap();
+        assert!(unsat.simplify_tautologies_and_units().is_none());
+    }
+
+    #[test]
+    fn polarity_summary_counts_occurrences() {
+        let formula = parse_text_cnf("a -b\n-a -b c\n").unwrap();
+        assert_eq!(formula.literals().count(), 5);
+        let summary = formula.polarity_summary();
+        assert_eq!(summary.len(), 3);
+        assert_eq!(summary["a"], (1, 1));
+        assert_eq!(summary["b"], (0, 2));
+        assert_eq!(summary["c"], (1, 0));
+    }
+
+    #[test]
+    fn clause_groups_only_hold_when_selected() {
+        use crate::solver::sink;
+        use crate::solver::CdclSolver;
+        use crate::solver::SolverResult;
+
+        // `s_1` is taken, so the groups get the next free selectors.
+        let mut formula = parse_text_cnf("a s_1\n").unwrap();
+        let first = formula.add_clause_group(parse_text_cnf("-a b\n").unwrap().clauses);
+        let second = formula.add_clause_group(parse_text_cnf("-b\n-s_1\n").unwrap().clauses);
+        assert_eq!(first.to_string(), "s_2");
+        assert_eq!(second.to_string(), "s_3");
+
+        let solve = |assumptions: &[&Literal]| {
+            let mut solver = CdclSolver::new(formula.clone());
+            solver.set_output(sink());
+            let assumptions: Vec<Literal> = assumptions.iter().map(|l| (*l).clone()).collect();
+            let result = solver.solve_under_assumptions(&assumptions);
+            if result == SolverResult::Satisfied {
+                assert!(solver.verify_model());
             }
+            (result, solver.unsat_core())
+        };
+        assert_eq!(solve(&[]).0, SolverResult::Satisfied);
+        assert_eq!(solve(&[&first]).0, SolverResult::Satisfied);
+        assert_eq!(solve(&[&second]).0, SolverResult::Satisfied);
+        assert_eq!(
+            solve(&[&first, &second.negate()]).0,
+            SolverResult::Satisfied
+        );
+        let (result, mut core) = solve(&[&first, &second]);
+        assert_eq!(result, SolverResult::Unresolved);
+        core.sort_by_key(|l| l.to_string());
+        assert_eq!(core, [first.clone(), second.clone()]);
+    }
+
+    #[test]
+    fn expr_constructors_build_their_variants() {
+        let (a, b) = (Expr::var("a"), Expr::var("b"));
+        let boxed = || (Box::new(a.clone()), Box::new(b.clone()));
+        assert_eq!(a, Expr::Var(String::from("a")));
+        assert_eq!(Expr::not(a.clone()), Expr::Not(Box::new(a.clone())));
+        let (x, y) = boxed();
+        assert_eq!(Expr::and(a.clone(), b.clone()), Expr::And(x, y));
+        let (x, y) = boxed();
+        assert_eq!(Expr::or(a.clone(), b.clone()), Expr::Or(x, y));
+        let (x, y) = boxed();
+        assert_eq!(Expr::implies(a.clone(), b.clone()), Expr::Implies(x, y));
+        let (x, y) = boxed();
+        assert_eq!(Expr::iff(a.clone(), b.clone()), Expr::Iff(x, y));
+        let (x, y) = boxed();
+        assert_eq!(Expr::xor(a.clone(), b.clone()), Expr::Xor(x, y));
+
+        let e = Expr::implies(
+            Expr::not(Expr::and(a.clone(), b.clone())),
+            Expr::iff(Expr::not(a.clone()), Expr::xor(a, b)),
+        );
+        assert_eq!(e.to_string(), "¬(a ∧ b) → (¬a ↔ (a ⊕ b))");
+    }
+
+    #[test]
+    fn interaction_graph_exports() {
+        // A repeated variable counts once per clause; a unit clause adds no
+        // edge, and numbers sort numerically before names.
+        let formula = parse_text_cnf("a b c\n-a b\nb b -c\nd\n10 2 a\n").unwrap();
+        assert_eq!(
+            formula.interaction_graph_edge_list(),
+            "2 10 1\n2 a 1\n10 a 1\na b 2\na c 1\nb c 2\n"
+        );
+        assert_eq!(
+            formula.interaction_graph_dot(),
+            "graph interaction {\n    \"2\" -- \"10\" [weight=1];\n    \"2\" -- \"a\" [weight=1];\n    \"10\" -- \"a\" [weight=1];\n    \"a\" -- \"b\" [weight=2];\n    \"a\" -- \"c\" [weight=1];\n    \"b\" -- \"c\" [weight=2];\n}\n"
+        );
+
+        let empty = parse_text_cnf("a\n").unwrap();
+        assert_eq!(empty.interaction_graph_edge_list(), "");
+        assert_eq!(empty.interaction_graph_dot(), "graph interaction {\n}\n");
+    }
+
+    #[test]
+    fn components_share_no_variables() {
+        let mut formula = parse_text_cnf("c d\na b\n-b e\n-d\n").unwrap();
+        formula.variables.insert(String::from("z"));
+        formula.clauses.push(Clause::new(Vec::new()));
+        let components = formula.connected_components();
+        let shown: Vec<(String, usize)> = components
+            .iter()
+            .map(|component| {
+                let mut variables: Vec<&String> = component.variables.iter().collect();
+                variables.sort();
+                let names: Vec<&str> = variables.iter().map(|v| v.as_str()).collect();
+                (names.join(" "), component.clauses.len())
+            })
+            .collect();
+        let expected = [("a b e", 2), ("c d", 2), ("z", 0), ("", 1)]
+            .map(|(variables, clauses)| (String::from(variables), clauses));
+        assert_eq!(shown, expected);
+    }
+
+    #[test]
+    fn elimination_orders_and_treewidths() {
+        let cases = [
+            // A path, eliminated from its ends.
+            ("a b\nb c\nc d\n", "a b c d", 1),
+            // A cycle needs its first elimination to join two neighbours.
+            ("a b\nb c\nc d\nd a\n", "a b c d", 2),
+            // One clause over four variables is a clique.
+            ("a b c d\n", "a b c d", 3),
+            // A star: leaves go first, numbers before names, until the
+            // centre has as few neighbours left as the last leaf.
+            ("h 10\nh 2\nh x\n", "2 10 h x", 1),
+            ("a\nb\n", "a b", 0),
+        ];
+        for (text, order, width) in cases {
+            let formula = parse_text_cnf(text).unwrap();
+            let found: Vec<String> = formula.min_degree_elimination_order();
+            assert_eq!(found.join(" "), order, "{}", text);
+            assert_eq!(formula.estimated_treewidth(), width, "{}", text);
         }
-        return result;
     }
 }
@@ -0,0 +1,96 @@
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+// The search log and result of a run, without the timing line.
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_cdcl"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with("Solved in"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn seeded_runs_repeat() {
+    let args = [
+        "testcases/aim-50-1_6-yes1-4.cnf",
+        "--heuristic",
+        "random",
+        "--seed",
+        "42",
+    ];
+    let first = run(&args);
+    assert!(first.starts_with("Using the random branching heuristic."));
+    assert_eq!(first, run(&args));
+}
+
+#[test]
+fn vsids_is_the_default_heuristic() {
+    let output = run(&["testcases/aim-50-1_6-yes1-4.cnf", "--verbosity", "info"]);
+    assert!(output.starts_with("Using the vsids branching heuristic."));
+}
+
+// The stdout and stderr of a run reading `input` from stdin.
+fn run_with_input(args: &[&str], input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cdcl"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn dimacs_model_reports_level_zero_conflicts() {
+    let input = "p cnf 2 3\n1 0\n-1 2 0\n-2 0\n";
+    let (stdout, stderr) = run_with_input(&["--dimacs-model"], input);
+    assert_eq!(stdout, "s UNSATISFIABLE\n");
+    assert!(stderr.contains("Formula is UNSAT by unit propagation through:"));
+}
+
+#[test]
+fn dimacs_model_keeps_the_log_off_stdout() {
+    let (stdout, _) = run_with_input(&["--dimacs-model"], "p cnf 2 2\n1 2 0\n-1 0\n");
+    assert_eq!(stdout, "s SATISFIABLE\nv -1 2 0\n");
+}
+
+#[test]
+fn level_zero_conflicts_print_their_chain() {
+    let input = "p cnf 2 3\n1 0\n-1 2 0\n-2 0\n";
+    let (stdout, _) = run_with_input(&["--verbosity", "silent"], input);
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "Formula is UNSAT by unit propagation through:");
+    // The parallel scan may find the units in another order.
+    lines[1..].sort();
+    assert_eq!(lines[1..], ["1", "¬1 ∨ 2", "¬2"]);
+}
+
+#[test]
+fn each_problem_of_a_file_is_solved() {
+    let input = "p cnf 1 1\n1 0\np cnf 1 2\n1 0\n-1 0\n";
+    let (stdout, _) = run_with_input(&["--verbosity", "silent", "--dimacs-model"], input);
+    assert_eq!(stdout, "s SATISFIABLE\nv 1 0\ns UNSATISFIABLE\n");
+    let (stdout, _) = run_with_input(&["--verbosity", "silent"], input);
+    assert!(stdout.starts_with("Problem 1:\nFormula is SAT"));
+    assert!(stdout.contains("Problem 2:\nFormula is UNSAT"));
+}
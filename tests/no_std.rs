@@ -0,0 +1,94 @@
+// Also run against the `no_std` build, with
+// `cargo test --no-default-features --test no_std`.
+use cdcl::config::SolverConfig;
+use cdcl::parse_dimacs_cnf;
+use cdcl::CdclSolver;
+use cdcl::SolverResult;
+
+#[test]
+fn solves_a_small_formula() {
+    let formula = parse_dimacs_cnf("p cnf 3 3\n1 2 0\n-1 3 0\n-3 0\n").unwrap();
+    let config = SolverConfig::default().seed(Some(1));
+    let mut solver = CdclSolver::with_config(formula, config);
+    solver.solve();
+    assert_eq!(*solver.sat(), SolverResult::Satisfied);
+    assert!(solver.verify_model());
+    assert_eq!(solver.value_of(&1), Some(false));
+    assert_eq!(solver.value_of(&2), Some(true));
+
+    let formula = parse_dimacs_cnf("p cnf 1 2\n1 0\n-1 0\n").unwrap();
+    let mut solver = CdclSolver::new(formula);
+    solver.solve();
+    assert_eq!(*solver.sat(), SolverResult::Unresolved);
+}
+
+// Without `std` the search log goes to any `fmt::Write`.
+#[cfg(not(feature = "std"))]
+#[test]
+fn logs_to_a_fmt_writer() {
+    use cdcl::config::Verbosity;
+    use std::fmt;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    struct Log(Arc<Mutex<String>>);
+
+    impl fmt::Write for Log {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.lock().unwrap().push_str(s);
+            Ok(())
+        }
+    }
+
+    let log = Arc::new(Mutex::new(String::new()));
+    let formula = parse_dimacs_cnf("p cnf 2 2\n1 0\n-1 2 0\n").unwrap();
+    let config = SolverConfig::default().verbosity(Verbosity::Trace);
+    let mut solver = CdclSolver::with_config(formula, config);
+    solver.set_output(Box::new(Log(log.clone())));
+    solver.solve();
+    assert!(log.lock().unwrap().contains("assigning 2 = true"));
+}
+
+// With `std` it goes to any `io::Write`, at the verbosity asked for.
+#[cfg(feature = "std")]
+#[test]
+fn logs_to_an_io_writer() {
+    use cdcl::config::Verbosity;
+    use std::io;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    struct Log(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Log {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let logged = |verbosity: Verbosity| {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let formula = parse_dimacs_cnf("p cnf 4 5\n1 0\n-1 2 0\n-2 3 0\n-3 1 0\n4 1 0\n").unwrap();
+        // Only 4 is pure.
+        let config = SolverConfig::default()
+            .pure_literals(true)
+            .verbosity(verbosity);
+        let mut solver = CdclSolver::with_config(formula, config);
+        solver.set_output(Box::new(Log(log.clone())));
+        solver.solve();
+        let log = log.lock().unwrap().clone();
+        String::from_utf8(log).unwrap()
+    };
+    assert_eq!(logged(Verbosity::Silent), "");
+    let info = logged(Verbosity::Info);
+    assert!(info.contains("Eliminated 1 pure literals"));
+    assert!(!info.contains("assigning"));
+    let trace = logged(Verbosity::Trace);
+    assert!(trace.contains("Eliminated 1 pure literals"));
+    assert!(trace.contains("Unit propagation, assigning 2 = true"));
+}